@@ -0,0 +1,88 @@
+//! Cost of the swept rope checks relative to the discrete pass alone, and of
+//! the broadphase against testing every pair as enemy counts grow.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use nannou::prelude::*;
+use survivor::sim::{
+    check_collisions, check_collisions_brute_force, Enemy, Hit, Rng, Rope, RopeParams,
+};
+
+const SUBSTEPS: i32 = 5;
+
+/// A rope mid-swing through a field of enemies, as in a busy late-game tick.
+/// The field grows with the count to keep the crowd equally dense.
+fn scene(enemy_count: usize) -> (Rope, Vec<Enemy>) {
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), RopeParams::default());
+    for (i, prev) in rope.prev_points.iter_mut().enumerate() {
+        *prev -= vec2(0.0, i as f32 * 4.0);
+    }
+
+    let scale = (enemy_count as f32 / 200.0).sqrt().max(1.0);
+    let (width, height) = (400.0 * scale, 300.0 * scale);
+    let mut rng = Rng::new(7);
+    let enemies = (0..enemy_count)
+        .map(|_| {
+            let position = pt2(rng.range(-width, width), rng.range(-height, height));
+            Enemy::new(
+                position,
+                rng.range(10.0, 20.0),
+                Rgba::new(1.0, 1.0, 1.0, 1.0),
+                10.0,
+            )
+        })
+        .collect();
+    (rope, enemies)
+}
+
+fn bench_collisions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("check_collisions");
+    for (name, continuous) in [("discrete", false), ("continuous", true)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || scene(200),
+                |(mut rope, mut enemies)| {
+                    black_box(check_collisions(
+                        &mut rope,
+                        &mut enemies,
+                        SUBSTEPS,
+                        continuous,
+                    ))
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+type Check = fn(&mut Rope, &mut [Enemy], i32, bool) -> Vec<Hit>;
+
+fn bench_broadphase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("broadphase");
+    group.sample_size(20);
+    let checks: [(&str, Check, usize); 2] = [
+        ("spatial_hash", check_collisions, 10_000),
+        // Quadratic; 10,000 enemies takes too long to sample
+        ("brute_force", check_collisions_brute_force, 1_000),
+    ];
+    for (name, check, max_count) in checks {
+        for count in [100, 1_000, 10_000] {
+            if count > max_count {
+                continue;
+            }
+            group.bench_with_input(BenchmarkId::new(name, count), &count, |b, &count| {
+                b.iter_batched(
+                    || scene(count),
+                    |(mut rope, mut enemies)| {
+                        black_box(check(&mut rope, &mut enemies, SUBSTEPS, true))
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_collisions, bench_broadphase);
+criterion_main!(benches);
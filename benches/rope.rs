@@ -0,0 +1,41 @@
+//! Cost of rope self-collision on long ropes, against the rope without it.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use nannou::prelude::*;
+use survivor::sim::{Rope, RopeParams};
+
+const DELTA_TIME: f32 = 1.0 / 60.0 / 5.0;
+
+/// A long rope coiled into a tight spiral, so most of it lies near other
+/// parts of itself.
+fn coil(segments: u32, self_collision: bool) -> Rope {
+    let params = RopeParams {
+        segments,
+        max_segments: segments,
+        self_collision,
+        ..RopeParams::default()
+    };
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(segments as f32 * 5.0, 0.0), params);
+    for (i, point) in rope.points.iter_mut().enumerate() {
+        let angle = i as f32 * 0.2;
+        *point = vec2(angle.cos(), angle.sin()) * (10.0 + angle * 2.0);
+    }
+    rope.prev_points = rope.points.clone();
+    rope
+}
+
+fn bench_self_collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rope_update");
+    for segments in [100, 1_000] {
+        for (name, self_collision) in [("free", false), ("self_collision", true)] {
+            let mut rope = coil(segments, self_collision);
+            group.bench_with_input(BenchmarkId::new(name, segments), &segments, |b, _| {
+                b.iter(|| rope.update(black_box(DELTA_TIME)))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_self_collision);
+criterion_main!(benches);
@@ -0,0 +1,134 @@
+use std::path::PathBuf;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage: survivor [--seed <u64>] [--tuning <file>] \
+                     [--tuning-set <key>=<value>]... [--enemies <file>] [--waves <file>] \
+                     [--upgrades <file>] [--rope <file>] [--rope-set <key>=<value>]... \
+                     [--settings <file>] [--record <file>] [--replay <file>]";
+
+/// Where every live run is saved when `--record` isn't given.
+const DEFAULT_RECORD_PATH: &str = "last-run.replay";
+
+/// Where rebound controls are saved when `--settings` isn't given.
+const DEFAULT_SETTINGS_PATH: &str = "settings.toml";
+
+/// Command-line options for the game binary.
+#[derive(Default)]
+pub struct Options {
+    pub seed: Option<u64>,
+    /// Gameplay constants to use instead of `assets/tuning.toml`.
+    pub tuning: Option<PathBuf>,
+    /// `key=value` assignments applied on top of the gameplay constants.
+    pub tuning_overrides: Vec<String>,
+    /// Enemy archetypes to use instead of `assets/enemies.toml`.
+    pub enemies: Option<PathBuf>,
+    /// Wave schedule to use instead of `assets/waves.toml`.
+    pub waves: Option<PathBuf>,
+    /// Level-up upgrades to use instead of `assets/upgrades.toml`.
+    pub upgrades: Option<PathBuf>,
+    /// Rope physics to use instead of `assets/rope.toml`.
+    pub rope: Option<PathBuf>,
+    /// `key=value` assignments applied on top of the rope physics.
+    pub rope_overrides: Vec<String>,
+    /// Player settings to use and save to instead of `settings.toml`.
+    pub settings: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+}
+
+impl Options {
+    /// Parses `std::env::args`, exiting with a usage message on bad input.
+    pub fn from_args() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{message}\n{USAGE}");
+                process::exit(2);
+            }
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().ok_or("--seed needs a value")?;
+                    let seed = value
+                        .parse()
+                        .map_err(|_| format!("invalid seed `{value}`"))?;
+                    options.seed = Some(seed);
+                }
+                "--tuning" => {
+                    let value = args.next().ok_or("--tuning needs a file")?;
+                    options.tuning = Some(value.into());
+                }
+                "--tuning-set" => {
+                    let value = args.next().ok_or("--tuning-set needs <key>=<value>")?;
+                    options.tuning_overrides.push(value);
+                }
+                "--enemies" => {
+                    let value = args.next().ok_or("--enemies needs a file")?;
+                    options.enemies = Some(value.into());
+                }
+                "--waves" => {
+                    let value = args.next().ok_or("--waves needs a file")?;
+                    options.waves = Some(value.into());
+                }
+                "--upgrades" => {
+                    let value = args.next().ok_or("--upgrades needs a file")?;
+                    options.upgrades = Some(value.into());
+                }
+                "--rope" => {
+                    let value = args.next().ok_or("--rope needs a file")?;
+                    options.rope = Some(value.into());
+                }
+                "--rope-set" => {
+                    let value = args.next().ok_or("--rope-set needs <key>=<value>")?;
+                    options.rope_overrides.push(value);
+                }
+                "--settings" => {
+                    let value = args.next().ok_or("--settings needs a file")?;
+                    options.settings = Some(value.into());
+                }
+                "--record" => {
+                    let value = args.next().ok_or("--record needs a file")?;
+                    options.record = Some(value.into());
+                }
+                "--replay" => {
+                    let value = args.next().ok_or("--replay needs a file")?;
+                    options.replay = Some(value.into());
+                }
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    process::exit(0);
+                }
+                _ => return Err(format!("unknown argument `{arg}`")),
+            }
+        }
+        Ok(options)
+    }
+
+    pub fn record_path(&self) -> PathBuf {
+        self.record
+            .clone()
+            .unwrap_or_else(|| DEFAULT_RECORD_PATH.into())
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.settings
+            .clone()
+            .unwrap_or_else(|| DEFAULT_SETTINGS_PATH.into())
+    }
+
+    /// The requested seed, or one derived from the clock for a fresh run.
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or_default()
+        })
+    }
+}
@@ -0,0 +1,130 @@
+//! Loading and validating the TOML data files designers edit.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Data that must be checked beyond what deserialization guarantees.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ConfigError>;
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    /// Parsed fine but the values don't make sense.
+    Invalid(String),
+    /// Any of the above, while loading `path`.
+    File {
+        path: PathBuf,
+        error: Box<ConfigError>,
+    },
+}
+
+impl ConfigError {
+    pub fn invalid(message: impl Into<String>) -> Self {
+        ConfigError::Invalid(message.into())
+    }
+
+    fn in_file(self, path: &Path) -> Self {
+        ConfigError::File {
+            path: path.to_owned(),
+            error: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "{err}"),
+            ConfigError::Parse(err) => write!(f, "{err}"),
+            ConfigError::Invalid(message) => write!(f, "{message}"),
+            ConfigError::File { path, error } => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            ConfigError::Parse(err) => Some(err),
+            ConfigError::Invalid(_) => None,
+            ConfigError::File { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Checks that `value` is above 0 and finite. The message names `field` and
+/// is left for the [`Validate`] impl to put in its own context.
+pub fn positive(field: &str, value: f32) -> Result<(), String> {
+    if value > 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(format!("{field} must be a positive number (got {value})"))
+    }
+}
+
+/// Checks that `value` is 0 or above and finite, like [`positive`].
+pub fn non_negative(field: &str, value: f32) -> Result<(), String> {
+    if value >= 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(format!("{field} must be 0 or more (got {value})"))
+    }
+}
+
+/// How a named entry is called in errors, even when its name was left empty.
+pub fn display_name(name: &str) -> &str {
+    if name.is_empty() {
+        "<unnamed>"
+    } else {
+        name
+    }
+}
+
+/// Parses and validates TOML text.
+pub fn parse<T: DeserializeOwned + Validate>(text: &str) -> Result<T, ConfigError> {
+    let value: T = toml::from_str(text).map_err(ConfigError::Parse)?;
+    value.validate()?;
+    Ok(value)
+}
+
+/// Reads, parses and validates a TOML file, naming the file in any error.
+pub fn load<T: DeserializeOwned + Validate>(path: impl AsRef<Path>) -> Result<T, ConfigError> {
+    let path = path.as_ref();
+    fs::read_to_string(path)
+        .map_err(ConfigError::Io)
+        .and_then(|text| parse(&text))
+        .map_err(|err| err.in_file(path))
+}
+
+/// Replaces top-level values of `value` with `key=value` assignments in TOML
+/// syntax, as given on the command line, and validates the result.
+pub fn with_overrides<T>(value: &T, overrides: &[String]) -> Result<T, ConfigError>
+where
+    T: Serialize + DeserializeOwned + Validate,
+{
+    let mut merged = match toml::Value::try_from(value) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => return Err(ConfigError::invalid("overrides need a table of values")),
+    };
+    for assignment in overrides {
+        // An assignment is itself a one-line TOML document
+        let table: toml::value::Table = toml::from_str(assignment)
+            .map_err(|err| ConfigError::invalid(format!("override `{assignment}`: {err}")))?;
+        merged.extend(table);
+    }
+    let value: T = toml::Value::Table(merged)
+        .try_into()
+        .map_err(ConfigError::Parse)?;
+    value.validate()?;
+    Ok(value)
+}
@@ -0,0 +1,105 @@
+use nannou::prelude::*;
+use survivor::sim::Event;
+
+/// Seconds a damage number stays on screen.
+const DAMAGE_NUMBER_LIFETIME: f32 = 0.8;
+/// Seconds a burst takes to fade out.
+const BURST_LIFETIME: f32 = 0.4;
+
+enum Effect {
+    DamageNumber {
+        position: Point2,
+        amount: f32,
+    },
+    Burst {
+        position: Point2,
+        radius: f32,
+        color: Rgba,
+    },
+}
+
+impl Effect {
+    fn lifetime(&self) -> f32 {
+        match self {
+            Effect::DamageNumber { .. } => DAMAGE_NUMBER_LIFETIME,
+            Effect::Burst { .. } => BURST_LIFETIME,
+        }
+    }
+}
+
+/// Short-lived visuals spawned from sim [`Event`]s. Purely cosmetic, so they
+/// run on real frame time rather than sim ticks.
+#[derive(Default)]
+pub struct Effects {
+    effects: Vec<(Effect, f32)>,
+}
+
+impl Effects {
+    pub fn extend(&mut self, events: impl IntoIterator<Item = Event>) {
+        for event in events {
+            let effect = match event {
+                Event::Damage { position, amount } => Effect::DamageNumber { position, amount },
+                Event::Kill {
+                    position,
+                    radius,
+                    color,
+                } => Effect::Burst {
+                    position,
+                    radius,
+                    color,
+                },
+                Event::Tear { position } => Effect::Burst {
+                    position,
+                    radius: 6.0,
+                    color: rgba(1.0, 1.0, 1.0, 1.0),
+                },
+                Event::Dash { position } => Effect::Burst {
+                    position,
+                    radius: 12.0,
+                    color: rgba(0.4, 0.8, 1.0, 1.0),
+                },
+            };
+            self.effects.push((effect, 0.0));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn update(&mut self, elapsed: f32) {
+        for (_, age) in self.effects.iter_mut() {
+            *age += elapsed;
+        }
+        self.effects
+            .retain(|(effect, age)| *age < effect.lifetime());
+    }
+
+    pub fn draw(&self, draw: &Draw) {
+        for (effect, age) in self.effects.iter() {
+            let t = age / effect.lifetime();
+            match effect {
+                Effect::DamageNumber { position, amount } => {
+                    draw.text(&format!("{amount:.0}"))
+                        .x_y(position.x, position.y + 40.0 * t)
+                        .color(rgba(1.0, 0.9, 0.3, 1.0 - t))
+                        .font_size(16);
+                }
+                Effect::Burst {
+                    position,
+                    radius,
+                    color,
+                } => {
+                    let mut color = *color;
+                    color.alpha = 1.0 - t;
+                    draw.ellipse()
+                        .x_y(position.x, position.y)
+                        .radius(radius * (1.0 + t))
+                        .no_fill()
+                        .stroke(color)
+                        .stroke_weight(3.0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Analog stick control: the left stick steers the head and the right stick
+//! flicks the rope tip.
+//!
+//! Controllers are read through the [`Gamepad`] trait so the frontend can use
+//! a real device (with the `gamepad` feature) and tests a scripted one.
+
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigError, Validate};
+use crate::sim::Input;
+
+/// Both analog sticks, each axis in `-1..=1` with up and right positive.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sticks {
+    pub left: Vec2,
+    pub right: Vec2,
+}
+
+/// A source of controller state, polled once per frame.
+pub trait Gamepad {
+    /// The sticks of the controller in use, or `None` if none is connected.
+    fn sticks(&mut self) -> Option<Sticks>;
+}
+
+/// How raw stick positions are shaped, from the `[gamepad]` table of
+/// `assets/settings.toml`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GamepadSettings {
+    /// Stick deflection, as a fraction of full tilt, that is ignored as drift.
+    pub dead_zone: f32,
+    /// Scale on deflection past the dead zone; above 1 reaches full speed
+    /// before full tilt.
+    pub sensitivity: f32,
+}
+
+impl GamepadSettings {
+    /// Drops deflection inside the dead zone and rescales the rest so output
+    /// still starts at 0 and tops out at length 1.
+    pub fn shape(&self, stick: Vec2) -> Vec2 {
+        let length = stick.length();
+        if length <= self.dead_zone {
+            return Vec2::ZERO;
+        }
+        let past = (length.min(1.0) - self.dead_zone) / (1.0 - self.dead_zone);
+        stick / length * (past * self.sensitivity).min(1.0)
+    }
+
+    /// Adds the sticks of `gamepad`, if one is connected, to a tick's input.
+    pub fn apply(&self, gamepad: &mut dyn Gamepad, input: &mut Input) {
+        let Some(sticks) = gamepad.sticks() else {
+            return;
+        };
+        input.movement = (input.movement + self.shape(sticks.left)).clamp_length_max(1.0);
+        input.flick = self.shape(sticks.right);
+    }
+}
+
+impl Validate for GamepadSettings {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if !(0.0..1.0).contains(&self.dead_zone) {
+            return Err(ConfigError::invalid(format!(
+                "gamepad: dead_zone must be at least 0 and below 1 (got {})",
+                self.dead_zone
+            )));
+        }
+        if !(self.sensitivity > 0.0 && self.sensitivity.is_finite()) {
+            return Err(ConfigError::invalid(format!(
+                "gamepad: sensitivity must be a positive number (got {})",
+                self.sensitivity
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gamepad")]
+pub use device::Controller;
+
+#[cfg(feature = "gamepad")]
+mod device {
+    use gilrs::{Axis, GamepadId, Gilrs};
+    use nannou::prelude::*;
+
+    use super::{Gamepad, Sticks};
+
+    /// Whichever connected controller was used last, read through gilrs.
+    pub struct Controller {
+        gilrs: Gilrs,
+        active: Option<GamepadId>,
+    }
+
+    impl Controller {
+        /// `None` if the platform's controller support can't be started.
+        pub fn new() -> Option<Self> {
+            let gilrs = Gilrs::new().ok()?;
+            Some(Controller {
+                gilrs,
+                active: None,
+            })
+        }
+    }
+
+    impl Gamepad for Controller {
+        fn sticks(&mut self) -> Option<Sticks> {
+            // Events keep gilrs' cached state current, and say who's playing
+            while let Some(event) = self.gilrs.next_event() {
+                self.active = Some(event.id);
+            }
+            let id = self
+                .active
+                .or_else(|| self.gilrs.gamepads().next().map(|(id, _)| id))?;
+            let pad = self.gilrs.connected_gamepad(id)?;
+            let stick = |x, y| vec2(pad.value(x), pad.value(y));
+            Some(Sticks {
+                left: stick(Axis::LeftStickX, Axis::LeftStickY),
+                right: stick(Axis::RightStickX, Axis::RightStickY),
+            })
+        }
+    }
+}
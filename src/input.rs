@@ -0,0 +1,476 @@
+//! Turning raw keys and buttons into player actions, and actions into the
+//! [`Input`] the simulation steps with.
+//!
+//! The frontend feeds every [`Button`] it sees to [`Actions`], which looks it
+//! up in the player's [`Bindings`]. Nothing past this point knows which device
+//! an action came from, so scripted or recorded input only needs actions.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigError, Validate};
+use crate::settings::Settings;
+use crate::sim::Input;
+
+/// Something the player can do, independent of how it's bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    /// Pull the head toward the cursor while held.
+    Drag,
+    /// Add a segment at the head, once per press.
+    Extend,
+    /// Take a segment away at the head, once per press.
+    Retract,
+    /// Burst of speed in the steered direction.
+    Dash,
+    Pause,
+    /// Start a new run once the current one is over.
+    Restart,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Drag,
+        Action::Extend,
+        Action::Retract,
+        Action::Dash,
+        Action::Pause,
+        Action::Restart,
+    ];
+
+    /// Name as written in config files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Drag => "drag",
+            Action::Extend => "extend",
+            Action::Retract => "retract",
+            Action::Dash => "dash",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+        }
+    }
+
+    /// Name shown in menus.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Drag => "Drag",
+            Action::Extend => "Extend rope",
+            Action::Retract => "Retract rope",
+            Action::Dash => "Dash",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart",
+        }
+    }
+}
+
+/// Opens and closes the controls menu. Not rebindable, so a bad binding can
+/// always be undone.
+pub const MENU_KEY: Key = Key::F1;
+
+/// Steps a paused run by one tick, for debugging. Not rebindable.
+pub const STEP_KEY: Key = Key::Period;
+
+/// Pick the first nine offered upgrades on the level-up screen. Not
+/// rebindable, as they match the numbers shown.
+pub const PICK_KEYS: [Key; 9] = [
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+];
+
+/// Keys that can be bound, written in config files by their variant name.
+const KEYS: &[Key] = &[
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::Numpad0,
+    Key::Numpad1,
+    Key::Numpad2,
+    Key::Numpad3,
+    Key::Numpad4,
+    Key::Numpad5,
+    Key::Numpad6,
+    Key::Numpad7,
+    Key::Numpad8,
+    Key::Numpad9,
+    Key::Up,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::Escape,
+    Key::Tab,
+    Key::Space,
+    Key::Return,
+    Key::Back,
+    Key::Insert,
+    Key::Delete,
+    Key::Home,
+    Key::End,
+    Key::PageUp,
+    Key::PageDown,
+    Key::LShift,
+    Key::RShift,
+    Key::LControl,
+    Key::RControl,
+    Key::LAlt,
+    Key::RAlt,
+    Key::Apostrophe,
+    Key::Backslash,
+    Key::Comma,
+    Key::Equals,
+    Key::Grave,
+    Key::LBracket,
+    Key::RBracket,
+    Key::Minus,
+    Key::Period,
+    Key::Semicolon,
+    Key::Slash,
+];
+
+/// A physical input that can be bound to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Button {
+    Key(Key),
+    Mouse(MouseButton),
+    /// One notch of the scroll wheel; pressed and released at once.
+    WheelUp,
+    WheelDown,
+}
+
+impl Button {
+    /// Whether the button is released the moment it's pressed.
+    pub fn is_momentary(self) -> bool {
+        matches!(self, Button::WheelUp | Button::WheelDown)
+    }
+
+    /// Whether the button can be written to a config file and read back.
+    pub fn is_bindable(self) -> bool {
+        self.to_string().parse() == Ok(self)
+    }
+
+    /// Whether the frontend handles the button itself, so it can't be bound
+    /// to an action.
+    pub fn is_reserved(self) -> bool {
+        match self {
+            Button::Key(key) => key == MENU_KEY || key == STEP_KEY || PICK_KEYS.contains(&key),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Button::Key(key) => write!(f, "{key:?}"),
+            Button::Mouse(MouseButton::Other(n)) => write!(f, "Mouse{n}"),
+            Button::Mouse(button) => write!(f, "Mouse{button:?}"),
+            Button::WheelUp => write!(f, "WheelUp"),
+            Button::WheelDown => write!(f, "WheelDown"),
+        }
+    }
+}
+
+impl FromStr for Button {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let button = match name {
+            "MouseLeft" => Button::Mouse(MouseButton::Left),
+            "MouseRight" => Button::Mouse(MouseButton::Right),
+            "MouseMiddle" => Button::Mouse(MouseButton::Middle),
+            "WheelUp" => Button::WheelUp,
+            "WheelDown" => Button::WheelDown,
+            _ if name.starts_with("Mouse") && name[5..].parse::<u16>().is_ok() => {
+                Button::Mouse(MouseButton::Other(name[5..].parse().unwrap()))
+            }
+            _ => {
+                let key = KEYS.iter().find(|key| format!("{key:?}") == name);
+                Button::Key(*key.ok_or_else(|| format!("unknown button `{name}`"))?)
+            }
+        };
+        Ok(button)
+    }
+}
+
+impl TryFrom<String> for Button {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+impl From<Button> for String {
+    fn from(button: Button) -> Self {
+        button.to_string()
+    }
+}
+
+/// Which buttons trigger each action, as in the `[bindings]` table of
+/// `assets/settings.toml`.
+// Keyed by name on disk, as the TOML crate can't use enums as table keys
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    try_from = "BTreeMap<String, Vec<Button>>",
+    into = "BTreeMap<String, Vec<Button>>"
+)]
+pub struct Bindings {
+    actions: BTreeMap<Action, Vec<Button>>,
+}
+
+impl Bindings {
+    pub fn buttons(&self, action: Action) -> &[Button] {
+        self.actions.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// The action `button` triggers, if any.
+    pub fn action(&self, button: Button) -> Option<Action> {
+        self.actions
+            .iter()
+            .find(|(_, buttons)| buttons.contains(&button))
+            .map(|(action, _)| *action)
+    }
+
+    /// Binds `button` to `action` alone, taking it away from whatever
+    /// action had it before.
+    pub fn rebind(&mut self, action: Action, button: Button) {
+        for buttons in self.actions.values_mut() {
+            buttons.retain(|bound| *bound != button);
+        }
+        self.actions.insert(action, vec![button]);
+    }
+
+    /// Leaves `action` without any button.
+    pub fn unbind(&mut self, action: Action) {
+        self.actions.insert(action, vec![]);
+    }
+
+    /// Gives actions that aren't listed, e.g. ones added since the file was
+    /// saved, their buttons in `defaults` that nothing else uses yet.
+    fn fill_from(&mut self, defaults: &Bindings) {
+        for (action, buttons) in defaults.actions.iter() {
+            if self.actions.contains_key(action) {
+                continue;
+            }
+            let free = buttons
+                .iter()
+                .filter(|button| self.action(**button).is_none())
+                .copied()
+                .collect();
+            self.actions.insert(*action, free);
+        }
+    }
+}
+
+impl TryFrom<BTreeMap<String, Vec<Button>>> for Bindings {
+    type Error = String;
+
+    fn try_from(named: BTreeMap<String, Vec<Button>>) -> Result<Self, Self::Error> {
+        let mut actions = BTreeMap::new();
+        for (name, buttons) in named {
+            let action = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == name)
+                .ok_or_else(|| format!("unknown action `{name}`"))?;
+            actions.insert(action, buttons);
+        }
+        let mut bindings = Bindings { actions };
+        // The shipped bindings list every action, so this doesn't recurse
+        if Action::ALL
+            .iter()
+            .any(|action| !bindings.actions.contains_key(action))
+        {
+            bindings.fill_from(&Settings::default().bindings);
+        }
+        Ok(bindings)
+    }
+}
+
+impl From<Bindings> for BTreeMap<String, Vec<Button>> {
+    fn from(bindings: Bindings) -> Self {
+        bindings
+            .actions
+            .into_iter()
+            .map(|(action, buttons)| (action.name().to_string(), buttons))
+            .collect()
+    }
+}
+
+impl Validate for Bindings {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut seen = BTreeMap::new();
+        for (action, buttons) in self.actions.iter() {
+            for button in buttons {
+                if button.is_reserved() {
+                    return Err(ConfigError::invalid(format!(
+                        "bindings: {button} is reserved and can't be bound to `{}`",
+                        action.name(),
+                    )));
+                }
+                if let Some(other) = seen.insert(button.to_string(), *action) {
+                    return Err(ConfigError::invalid(format!(
+                        "bindings: {button} is bound to both `{}` and `{}`",
+                        other.name(),
+                        action.name(),
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What the player is doing, gathered from button events between ticks.
+#[derive(Clone, Debug, Default)]
+pub struct Actions {
+    /// Buttons down for each held action. An action bound to several stays
+    /// held until the last of them is let go.
+    held: BTreeMap<Action, Vec<Button>>,
+    /// Presses not yet taken, per action.
+    presses: BTreeMap<Action, u32>,
+}
+
+impl Actions {
+    /// Records `button` going down, returning the action it triggered.
+    pub fn press(&mut self, button: Button, bindings: &Bindings) -> Option<Action> {
+        let action = bindings.action(button)?;
+        if button.is_momentary() {
+            *self.presses.entry(action).or_default() += 1;
+            return Some(action);
+        }
+        let held = self.held.entry(action).or_default();
+        // Key repeat sends more presses while held; only the first counts
+        if !held.contains(&button) {
+            held.push(button);
+            *self.presses.entry(action).or_default() += 1;
+        }
+        Some(action)
+    }
+
+    pub fn release(&mut self, button: Button, bindings: &Bindings) {
+        let Some(action) = bindings.action(button) else {
+            return;
+        };
+        if let Some(held) = self.held.get_mut(&action) {
+            held.retain(|down| *down != button);
+            if held.is_empty() {
+                self.held.remove(&action);
+            }
+        }
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains_key(&action)
+    }
+
+    /// Presses of `action` since it was last taken.
+    pub fn take_presses(&mut self, action: Action) -> u32 {
+        self.presses.remove(&action).unwrap_or_default()
+    }
+
+    /// Lets go of everything, e.g. when a menu takes over the keyboard.
+    pub fn clear(&mut self) {
+        self.held.clear();
+        self.presses.clear();
+    }
+
+    /// Direction of the held movement actions, of length 0 or 1.
+    pub fn movement(&self) -> Vec2 {
+        let axis = |negative, positive| {
+            self.is_held(positive) as i32 as f32 - self.is_held(negative) as i32 as f32
+        };
+        vec2(
+            axis(Action::MoveLeft, Action::MoveRight),
+            axis(Action::MoveDown, Action::MoveUp),
+        )
+        .normalize_or_zero()
+    }
+
+    /// The simulation input for the next tick, taking the rope and dash
+    /// presses.
+    pub fn tick_input(&mut self, cursor: Point2) -> Input {
+        let extend = self.take_presses(Action::Extend) as i32;
+        let retract = self.take_presses(Action::Retract) as i32;
+        Input {
+            drag_index: self.is_held(Action::Drag).then_some(0),
+            cursor,
+            extend: (extend - retract).clamp(i8::MIN as i32, i8::MAX as i32) as i8,
+            movement: self.movement(),
+            flick: Vec2::ZERO,
+            dash: self.take_presses(Action::Dash) > 0,
+            upgrade: None,
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! Game logic for survivor, independent of the nannou window.
+//!
+//! The binary in `main.rs` is a thin frontend around [`sim::World`]; everything
+//! here can be stepped headlessly from tests, benchmarks and CI.
+
+pub mod config;
+pub mod gamepad;
+pub mod input;
+pub mod replay;
+pub mod scores;
+pub mod settings;
+pub mod sim;
+pub mod state;
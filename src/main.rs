@@ -1,7 +1,31 @@
-use nannou::{prelude::*, rand::random_f32};
+mod cli;
+mod effects;
+mod menu;
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+use effects::Effects;
+use menu::ControlsMenu;
+use nannou::prelude::*;
+use serde::de::DeserializeOwned;
+use survivor::config::{self, Validate};
+use survivor::gamepad::Gamepad;
+use survivor::input::{Action, Actions, Button, MENU_KEY, PICK_KEYS, STEP_KEY};
+use survivor::replay::{Divergence, Player, Recorder, Replay};
+use survivor::scores::{self, Entry, HighScores};
+use survivor::settings::Settings;
+use survivor::sim::{FixedTimestep, Input, Tuning, World, TICK};
+use survivor::state::State;
+
+/// Seconds skipped by the seek keys during playback.
+const SEEK_SECONDS: f32 = 5.0;
+
+/// Trackpad scrolling, in pixels, that counts as one wheel notch.
+const PIXELS_PER_NOTCH: f32 = 40.0;
 
 fn main() {
-    nannou::app(model).update(update).run();
+    nannou::app(model).update(update).exit(exit).run();
 }
 
 fn model(app: &App) -> Model {
@@ -9,314 +33,752 @@ fn model(app: &App) -> Model {
         .view(view)
         .mouse_pressed(mouse_pressed)
         .mouse_released(mouse_released)
+        .mouse_wheel(mouse_wheel)
+        .key_pressed(key_pressed)
+        .key_released(key_released)
+        .unfocused(unfocused)
         .build()
         .unwrap();
 
-    let start = Point2::new(0.0, 0.0);
-    let end = Point2::new(100.0, 0.0);
-    let count = 12;
+    let options = cli::Options::from_args();
+    let tuning = load_tuning(app, &options);
+    let settings = load_settings(app, &options);
+
+    // Replays skip the title and have their own pause
+    let (session, state) = match &options.replay {
+        Some(path) => match Replay::load(path) {
+            Ok(replay) => (Session::Replay(Player::new(replay)), State::Playing),
+            Err(err) => {
+                eprintln!("failed to load replay {}: {err}", path.display());
+                process::exit(1);
+            }
+        },
+        None => {
+            let run = Run::new(app.window_rect(), options.seed(), tuning.clone());
+            (Session::Live(run), State::Title)
+        }
+    };
+
+    let scores_path = scores::path();
+    let scores = match &scores_path {
+        Some(path) => {
+            let (scores, error) = HighScores::load(path);
+            if let Some(err) = error {
+                eprintln!("high scores {}: {err}", path.display());
+            }
+            scores
+        }
+        None => HighScores::default(),
+    };
 
     Model {
-        rope: Rope::new(start, end, count),
-        enemies: vec![],
-        is_dragging: false,
-        drag_index: Some(0),
-        enemy_timer: 0.0,
-        spawn_delay: 0.5,
-        score: 0,
-    }
-}
-
-struct Rope {
-    points: Vec<Point2>,
-    prev_points: Vec<Point2>,
-    segment_length: f32,
-    thickness: f32,
-    color: Rgba,
-}
-
-impl Rope {
-    fn new(start: Point2, end: Point2, count: usize) -> Self {
-        let length = start.distance(end);
-        let segment_length = length / (count as f32 - 1.0);
-        let direction = (end - start).normalize();
-
-        let points: Vec<Point2> = (0..count)
-            .map(|i| start + direction * segment_length * i as f32)
-            .collect();
-
-        let prev_points = points.clone();
-
-        Rope {
-            points,
-            prev_points,
-            segment_length,
-            thickness: 4.0,
-            color: nannou::color::Rgba::new(1.0, 1.0, 1.0, 1.0),
-        }
+        session,
+        state,
+        scores,
+        scores_path,
+        rank: None,
+        pick: None,
+        actions: Actions::default(),
+        scroll: 0.0,
+        menu: None,
+        gamepad: connect_gamepad(),
+        effects: Effects::default(),
+        tuning,
+        settings,
+        options,
     }
+}
 
-    fn update(&mut self, substeps: i32) {
-        self.update_rope(substeps);
+/// Tuning for live runs, with gameplay constants, rope physics, enemy
+/// archetypes, the wave schedule and upgrades read from
+/// `--tuning`/`--rope`/`--enemies`/`--waves`/`--upgrades` or the shipped files
+/// in `assets/`, and any `--tuning-set`/`--rope-set` overrides on top.
+fn load_tuning(app: &App, options: &cli::Options) -> Tuning {
+    let mut tuning: Tuning = match options.tuning.clone().or_else(|| asset(app, "tuning.toml")) {
+        Some(path) => load_or_exit(&path),
+        None => Tuning::default(),
+    };
+    tuning = config::with_overrides(&tuning, &options.tuning_overrides).unwrap_or_else(|err| {
+        eprintln!("--tuning-set: {err}");
+        process::exit(1);
+    });
+    if let Some(path) = options.rope.clone().or_else(|| asset(app, "rope.toml")) {
+        tuning.rope = load_or_exit(&path);
+    }
+    tuning.rope =
+        config::with_overrides(&tuning.rope, &options.rope_overrides).unwrap_or_else(|err| {
+            eprintln!("--rope-set: {err}");
+            process::exit(1);
+        });
+    if let Some(path) = options
+        .enemies
+        .clone()
+        .or_else(|| asset(app, "enemies.toml"))
+    {
+        tuning.archetypes = load_or_exit(&path);
+    }
+    if let Some(path) = options.waves.clone().or_else(|| asset(app, "waves.toml")) {
+        tuning.waves = load_or_exit(&path);
     }
+    if let Some(path) = options
+        .upgrades
+        .clone()
+        .or_else(|| asset(app, "upgrades.toml"))
+    {
+        tuning.upgrades = load_or_exit(&path);
+    }
+    // Each file is valid alone; this catches waves naming unknown archetypes
+    if let Err(err) = tuning.validate() {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+    tuning
+}
 
-    fn update_rope(&mut self, substeps: i32) {
-        for i in 1..self.points.len() {
-            let current = self.points[i];
-            let prev = self.prev_points[i];
-            let velocity = current - prev;
-            let next_position = current + velocity / 1.008; // Apply gravity here if needed
-            self.prev_points[i] = self.points[i];
-            self.points[i] = next_position;
-        }
+/// The player's saved settings if there are any, or else the shipped ones.
+fn load_settings(app: &App, options: &cli::Options) -> Settings {
+    let saved = options.settings_path();
+    if options.settings.is_some() || saved.exists() {
+        return load_or_exit(&saved);
+    }
+    match asset(app, "settings.toml") {
+        Some(path) => load_or_exit(&path),
+        None => Settings::default(),
+    }
+}
 
-        for _ in 0..substeps {
-            self.constrain_points();
+/// Controller support, when built with the `gamepad` feature.
+fn connect_gamepad() -> Option<Box<dyn Gamepad>> {
+    #[cfg(feature = "gamepad")]
+    {
+        let controller = survivor::gamepad::Controller::new();
+        if controller.is_none() {
+            eprintln!("gamepad support could not be started");
         }
+        controller.map(|controller| Box::new(controller) as Box<dyn Gamepad>)
     }
+    #[cfg(not(feature = "gamepad"))]
+    None
+}
 
-    fn constrain_points(&mut self) {
-        let count = self.points.len();
-        for _ in 0..3 {
-            for i in 0..(count - 1) {
-                let point_a = self.points[i];
-                let point_b = self.points[i + 1];
-                let delta = point_b - point_a;
-                let distance = delta.length();
-                let difference = self.segment_length - distance;
-                let correction = delta.normalize() * (difference / 15.0);
-                if i != 0 {
-                    self.points[i] -= correction;
-                }
-                self.points[i + 1] += correction;
-            }
+/// Path of a shipped data file, if the assets directory can be found.
+fn asset(app: &App, name: &str) -> Option<PathBuf> {
+    app.assets_path().ok().map(|assets| assets.join(name))
+}
+
+fn load_or_exit<T: DeserializeOwned + Validate>(path: &Path) -> T {
+    config::load(path).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(1);
+    })
+}
+
+struct Model {
+    session: Session,
+    state: State,
+    scores: HighScores,
+    /// Where `scores` is saved, if there is anywhere.
+    scores_path: Option<PathBuf>,
+    /// Place of the last finished run in `scores`, if it made the table.
+    rank: Option<usize>,
+    /// Upgrade picked on the level-up screen, for the next tick to take.
+    pick: Option<u8>,
+    actions: Actions,
+    /// Scrolling not yet turned into whole wheel notches.
+    scroll: f32,
+    /// Open while rebinding; holds the run still and takes all input.
+    menu: Option<ControlsMenu>,
+    gamepad: Option<Box<dyn Gamepad>>,
+    effects: Effects,
+    tuning: Tuning,
+    settings: Settings,
+    options: cli::Options,
+}
+
+enum Session {
+    Live(Run),
+    Replay(Player),
+}
+
+/// A live run and its recording.
+struct Run {
+    world: World,
+    timestep: FixedTimestep,
+    recorder: Recorder,
+}
+
+impl Run {
+    fn new(arena: Rect, seed: u64, tuning: Tuning) -> Self {
+        let world = World::with_tuning(arena, seed, tuning);
+        Run {
+            recorder: Recorder::new(&world),
+            world,
+            timestep: FixedTimestep::new(TICK),
         }
     }
 
-    fn get_segment_midpoints(&self) -> Vec<Point2> {
-        let mut midpoints = vec![];
-        for i in 0..(self.points.len() - 1) {
-            let midpoint = (self.points[i] + self.points[i + 1]) * 0.5;
-            midpoints.push(midpoint);
-        }
-        midpoints
+    fn advance(&mut self, input: Input, ticks: u32) {
+        self.recorder.advance(&mut self.world, input, ticks);
     }
-}
 
-struct Enemy {
-    position: Point2,
-    prev_position: Point2,
-    radius: f32,
-    color: Rgba,
+    fn save(self, path: &Path) {
+        let replay = self.recorder.finish(&self.world);
+        if let Err(err) = replay.save(path) {
+            eprintln!("failed to save replay {}: {err}", path.display());
+        }
+    }
 }
 
-impl Enemy {
-    fn new(position: Point2, radius: f32, color: Rgba) -> Self {
-        Enemy {
-            position,
-            prev_position: position,
-            radius,
-            color,
+impl Model {
+    fn world(&self) -> &World {
+        match &self.session {
+            Session::Live(run) => &run.world,
+            Session::Replay(player) => player.world(),
         }
     }
 
-    fn update(&mut self, target: Point2, delta_time: f32) {
-        let current = self.position;
-        let prev = self.prev_position;
-        let velocity = current - prev;
-        self.prev_position = current;
-
-        // Move towards the target (first point of the rope)
-        let direction = (target - current).normalize();
-        let next_position = current + velocity + direction * delta_time;
-        self.position = next_position;
+    fn alpha(&self) -> f32 {
+        match &self.session {
+            Session::Live(run) => run.timestep.alpha(),
+            Session::Replay(player) => player.alpha(),
+        }
     }
 }
 
-struct Model {
-    enemies: Vec<Enemy>,
-    rope: Rope,
-    is_dragging: bool,
-    drag_index: Option<usize>,
-    enemy_timer: f32,
-    spawn_delay: f32,
-    score: i32,
-}
-
-fn update(_app: &App, model: &mut Model, _update: Update) {
-    model.enemy_timer += 0.01;
-    let substeps = 5; // Number of substeps for more accurate updates
-    let delta_time = 0.01 / substeps as f32;
-
-    let target_position = model.rope.points[0];
-    for _ in 0..substeps {
-        model.rope.update(substeps);
-        if model.is_dragging {
-            if let Some(index) = model.drag_index {
-                let cursor_position = _app.mouse.position();
-                let current_position = model.rope.points[index];
-                let lerp_position = lerp(current_position, cursor_position, 0.06);
-                model.rope.points[index] = lerp_position;
+fn update(app: &App, model: &mut Model, update: Update) {
+    let elapsed = update.since_last.as_secs_f32();
+    match &mut model.session {
+        Session::Live(run) => {
+            if !model.state.is_running() || model.menu.is_some() {
+                return;
+            }
+            run.world.arena = app.window_rect();
+            let ticks = run.timestep.advance(elapsed);
+            if ticks > 0 {
+                let mut input =
+                    tick_input(app, &mut model.actions, &model.settings, &mut model.gamepad);
+                input.upgrade = model.pick.take();
+                run.advance(input, ticks);
+                // Not on frames without a tick, which would see the offer
+                // still open and drop a pick waiting for the next one
+                end_step(model);
             }
         }
-
-        // Update enemies to move towards the first rope point
-        for enemy in model.enemies.iter_mut() {
-            enemy.update(target_position, delta_time);
+        Session::Replay(player) => {
+            if let Err(divergence) = player.advance(elapsed) {
+                fail_replay(divergence);
+            }
+            // Seeking back can leave the game over again
+            model.state = State::Playing.after_step(player.world());
+            model.effects.extend(player.take_events());
         }
+    }
+    model.effects.update(elapsed);
+}
+
+/// The input for the next tick from the actions, the controls the player
+/// allows and the gamepad.
+fn tick_input(
+    app: &App,
+    actions: &mut Actions,
+    settings: &Settings,
+    gamepad: &mut Option<Box<dyn Gamepad>>,
+) -> Input {
+    let controls = settings.controls;
+    let mut input = actions.tick_input(app.mouse.position());
+    if !controls.mouse() {
+        input.drag_index = None;
+    }
+    if !controls.keyboard() {
+        input.movement = Vec2::ZERO;
+    }
+    if let Some(gamepad) = gamepad {
+        settings.gamepad.apply(gamepad.as_mut(), &mut input);
+    }
+    input
+}
 
-        // Check for collisions
-        check_collisions(&mut model.rope, &mut model.enemies, substeps);
+/// Moves on to the game over or level-up screen if the live run just called
+/// for it, and shows what happened in it.
+fn end_step(model: &mut Model) {
+    if let Session::Live(run) = &mut model.session {
+        let state = model.state.after_step(&run.world);
+        model.effects.extend(run.world.events.drain(..));
+        if state == State::GameOver && model.state != State::GameOver {
+            model.rank = model.scores.insert(Entry::new(&run.world));
+            save_scores(model);
+        }
+        model.state = state;
     }
+}
 
-    spawn_enemies(_app, model);
-    despawn_enemies(_app, model);
+fn save_scores(model: &Model) {
+    let Some(path) = &model.scores_path else {
+        return;
+    };
+    if let Err(err) = model.scores.save(path) {
+        eprintln!("failed to save high scores {}: {err}", path.display());
+    }
 }
 
-fn check_collisions(rope: &mut Rope, enemies: &mut [Enemy], substeps: i32) {
-    let midpoints = rope.get_segment_midpoints();
+fn exit(_app: &App, model: Model) {
+    if let Session::Live(run) = model.session {
+        run.save(&model.options.record_path());
+    }
+}
 
-    for enemy in enemies.iter_mut() {
-        for point in rope.points.iter_mut() {
-            let distance = enemy.position.distance(*point + vec2(rope.thickness, 0.0));
-            if distance < enemy.radius {
-                // Simple collision response: move both enemy and rope point away from each other
-                let direction = (enemy.position - *point).normalize();
-                let overlap = (enemy.radius - distance) / substeps as f32;
-                enemy.position += direction * overlap * 0.5;
-                *point -= direction * overlap * 0.5;
-            }
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if key == MENU_KEY && model.menu.is_none() {
+        model.menu = Some(ControlsMenu::default());
+        model.actions.clear();
+        return;
+    }
+    if key == STEP_KEY && model.state == State::Paused && model.menu.is_none() {
+        if let Session::Live(run) = &mut model.session {
+            let input = tick_input(app, &mut model.actions, &model.settings, &mut model.gamepad);
+            run.advance(input, 1);
         }
+        end_step(model);
+        return;
+    }
+    press(app, model, Button::Key(key));
+}
+
+fn key_released(_app: &App, model: &mut Model, key: Key) {
+    model
+        .actions
+        .release(Button::Key(key), &model.settings.bindings);
+}
 
-        for midpoint in midpoints.iter() {
-            let distance = enemy.position.distance(*midpoint);
-            let dynamic_thickness = rope.segment_length / 2.0;
-            if distance < enemy.radius + dynamic_thickness {
-                let direction = (enemy.position - *midpoint).normalize();
-                let overlap = (enemy.radius + dynamic_thickness - distance) / substeps as f32;
-                enemy.position += direction * overlap * 0.5;
+/// Sends a button to the open menu, or else to the actions of a live run or
+/// the playback controls.
+fn press(app: &App, model: &mut Model, button: Button) {
+    if let Some(menu) = &mut model.menu {
+        if !menu.press(button, &mut model.settings.bindings) {
+            model.menu = None;
+            let path = model.options.settings_path();
+            if let Err(err) = model.settings.save(&path) {
+                eprintln!("failed to save settings {}: {err}", path.display());
             }
         }
+        return;
     }
 
-    for i in 0..enemies.len() {
-        for j in i + 1..enemies.len() {
-            let distance = enemies[i].position.distance(enemies[j].position);
-            if distance < enemies[i].radius + enemies[j].radius {
-                // Simple collision response: move both enemies away from each other
-                let direction = (enemies[i].position - enemies[j].position).normalize();
-                let overlap = (enemies[i].radius + enemies[j].radius - distance) / substeps as f32;
-                enemies[i].position += direction * overlap * 0.5;
-                enemies[j].position -= direction * overlap * 0.5;
+    match &mut model.session {
+        Session::Live(run) => {
+            if model.state == State::LevelUp {
+                let pick = PICK_KEYS
+                    .iter()
+                    .position(|key| button == Button::Key(*key))
+                    .filter(|pick| *pick < run.world.choices.len());
+                if let Some(pick) = pick {
+                    model.pick = Some(pick as u8);
+                    model.state = State::Playing;
+                }
+                return;
+            }
+            let action = model.actions.press(button, &model.settings.bindings);
+            match (model.state, action) {
+                (State::Title, _) => model.state = State::Playing,
+                (_, Some(Action::Pause)) => model.state = model.state.toggle_pause(),
+                (State::GameOver, Some(Action::Restart)) => {
+                    let next = Run::new(
+                        app.window_rect(),
+                        model.options.seed(),
+                        model.tuning.clone(),
+                    );
+                    std::mem::replace(run, next).save(&model.options.record_path());
+                    model.effects.clear();
+                    model.state = State::Playing;
+                }
+                _ => {}
             }
         }
+        Session::Replay(player) => {
+            if let Button::Key(key) = button {
+                if let Err(divergence) = replay_key(player, key) {
+                    fail_replay(divergence);
+                }
+            }
+        }
+    }
+}
+
+fn replay_key(player: &mut Player, key: Key) -> Result<(), Divergence> {
+    let seek_ticks = (SEEK_SECONDS / TICK) as u64;
+    match key {
+        Key::Space => player.paused = !player.paused,
+        Key::Period if player.paused => player.step()?,
+        Key::Left => player.seek(player.world().tick.saturating_sub(seek_ticks))?,
+        Key::Right => player.seek(player.world().tick + seek_ticks)?,
+        Key::Up => player.set_speed(player.speed() * 2.0),
+        Key::Down => player.set_speed(player.speed() / 2.0),
+        _ => {}
     }
+    Ok(())
 }
 
-fn mouse_pressed(_app: &App, model: &mut Model, _button: MouseButton) {
-    model.is_dragging = true;
-    model.drag_index = Some(0); // Drag the first point
+/// A replay that no longer matches is a determinism bug; stop with the report
+/// rather than keep showing a run that never happened.
+fn fail_replay(divergence: Divergence) -> ! {
+    eprintln!("{divergence}");
+    process::exit(1);
 }
 
-fn mouse_released(_app: &App, model: &mut Model, _button: MouseButton) {
-    model.is_dragging = false;
-    model.drag_index = None;
+fn unfocused(_app: &App, model: &mut Model) {
+    // Buttons let go of while unfocused are never seen released
+    model.actions.clear();
+    match &mut model.session {
+        Session::Live(_) => model.state = model.state.unfocus(),
+        Session::Replay(player) => player.paused = true,
+    }
+}
+
+fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
+    press(app, model, Button::Mouse(button));
+}
+
+fn mouse_released(_app: &App, model: &mut Model, button: MouseButton) {
+    model
+        .actions
+        .release(Button::Mouse(button), &model.settings.bindings);
+}
+
+fn mouse_wheel(app: &App, model: &mut Model, delta: MouseScrollDelta, _phase: TouchPhase) {
+    model.scroll += match delta {
+        MouseScrollDelta::LineDelta(_, y) => y,
+        MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_NOTCH,
+    };
+    // Each whole notch is a press of its own
+    while model.scroll.abs() >= 1.0 {
+        let notch = model.scroll.signum();
+        model.scroll -= notch;
+        let button = if notch > 0.0 {
+            Button::WheelUp
+        } else {
+            Button::WheelDown
+        };
+        press(app, model, button);
+    }
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
+    let win = app.window_rect();
+
     // Begin drawing
     let draw = app.draw();
 
     // Clear the background to black.
     draw.background().color(BLACK);
 
-    // Apply camera transformation
+    if model.state.shows_run() {
+        draw_run(app, &draw, model);
+    } else {
+        draw_title(&draw, win, &model.scores);
+    }
+
+    match model.state {
+        State::GameOver => {
+            let restart = match model.session {
+                Session::Live(_) => model.settings.bindings.buttons(Action::Restart).first(),
+                Session::Replay(_) => None,
+            };
+            draw_game_over(&draw, model.world(), restart);
+            if let Session::Live(_) = model.session {
+                draw_scores(&draw, &model.scores, model.rank, -150.0);
+            }
+        }
+        State::Paused => {
+            draw.text("PAUSED")
+                .x_y(0.0, 0.0)
+                .color(WHITE)
+                .font_size(40)
+                .w(400.0);
+            draw.text(&format!("press {STEP_KEY:?} to step one tick"))
+                .x_y(0.0, -40.0)
+                .color(GRAY)
+                .font_size(14)
+                .w(400.0);
+        }
+        State::LevelUp => draw_level_up(&draw, model.world()),
+        State::Title | State::Playing => {}
+    }
+    if let Some(menu) = &model.menu {
+        menu.draw(&draw, &model.settings.bindings);
+    }
+
+    // Write the result of our drawing to the window's frame.
+    draw.to_frame(app, &frame).unwrap();
+}
 
-    for (i, point) in model.rope.points.iter().enumerate() {
-        let radius = if i == 0 || i == model.rope.points.len() - 1 {
-            model.rope.thickness * 2.0 // First and last points are larger
+/// The arena, the run in it and the HUD over it.
+fn draw_run(app: &App, draw: &Draw, model: &Model) {
+    let world = model.world();
+    let alpha = model.alpha();
+    let win = app.window_rect();
+
+    let points = world.rope.interpolated_points(alpha);
+    let attached = world.rope.attached_len();
+    for (i, point) in points.iter().enumerate() {
+        let radius = if i == 0 || i == points.len() - 1 {
+            world.rope.head_radius() // First and last points are larger
+        } else {
+            world.rope.thickness()
+        };
+
+        // Blink the head while it can't be hurt
+        let blink = i == 0 && world.health.is_invulnerable() && (app.time * 10.0) as i32 % 2 == 0;
+        let color = if blink {
+            rgba(1.0, 0.2, 0.2, 1.0)
+        } else if i >= attached {
+            // Torn off and waiting to be picked back up
+            let mut color = world.rope.color;
+            color.alpha = 0.4;
+            color
         } else {
-            model.rope.thickness
+            world.rope.color
         };
 
         draw.ellipse()
             .x_y(point.x, point.y)
             .radius(radius)
-            .color(model.rope.color);
+            .color(color);
     }
-    for enemy in model.enemies.iter() {
+    for enemy in world.enemies.iter() {
+        let position = enemy.interpolated_position(alpha);
         draw.ellipse()
-            .x_y(enemy.position.x, enemy.position.y)
+            .x_y(position.x, position.y)
             .radius(enemy.radius)
             .color(enemy.color);
+        if enemy.constricted {
+            draw.ellipse()
+                .x_y(position.x, position.y)
+                .radius(enemy.radius + 3.0)
+                .no_fill()
+                .stroke(WHITE)
+                .stroke_weight(2.0);
+        }
+    }
+    for orb in world.orbs.iter() {
+        let position = orb.interpolated_position(alpha);
+        draw.ellipse()
+            .x_y(position.x, position.y)
+            .radius(orb.radius())
+            .color(rgba(0.3, 1.0, 0.5, 1.0));
     }
 
-    draw.text(&model.score.to_string())
-        .x_y(
-            -app.window_rect().right() + 50.0,
-            app.window_rect().top() - 50.0,
-        )
+    model.effects.draw(draw);
+
+    draw.text(&world.score.to_string())
+        .x_y(-win.right() + 50.0, win.top() - 50.0)
         .color(WHITE)
         .font_size(48);
 
-    // Write the result of our drawing to the window's frame.
-    draw.to_frame(app, &frame).unwrap();
+    draw_health_bar(draw, win, world);
+    draw_dash_bar(draw, win, world);
+    draw_xp_bar(draw, win, world);
+
+    draw.text(&format!("seed {}", world.seed))
+        .x_y(-win.right() + 100.0, win.bottom() + 20.0)
+        .color(GRAY)
+        .font_size(14);
+
+    if let Session::Replay(player) = &model.session {
+        let status = format!(
+            "replay {:.1}s / {:.1}s  x{}{}",
+            world.tick as f32 * TICK,
+            player.len() as f32 * TICK,
+            player.speed(),
+            if player.paused { "  paused" } else { "" },
+        );
+        draw.text(&status)
+            .x_y(0.0, win.bottom() + 20.0)
+            .color(GRAY)
+            .font_size(14);
+    }
 }
 
-fn lerp(a: Point2, b: Point2, t: f32) -> Point2 {
-    let x = a.x + (b.x - a.x) * t;
-    let y = a.y + (b.y - a.y) * t;
-    Point2::new(x, y)
+fn draw_title(draw: &Draw, win: Rect, scores: &HighScores) {
+    draw.text("SURVIVOR")
+        .x_y(0.0, 60.0)
+        .color(WHITE)
+        .font_size(64)
+        .w(win.w());
+    draw.text("press any button to start")
+        .x_y(0.0, -20.0)
+        .color(GRAY)
+        .font_size(20)
+        .w(win.w());
+    draw.text(&format!("{MENU_KEY:?} controls"))
+        .x_y(0.0, -60.0)
+        .color(GRAY)
+        .font_size(14)
+        .w(win.w());
+    draw_scores(draw, scores, None, -110.0);
 }
 
-fn spawn_enemies(app: &App, model: &mut Model) {
-    if model.enemy_timer >= model.spawn_delay {
-        let win = app.window_rect();
-        let margin = 1.0; // Margin outside the window
-        let (x, y) = if random_f32() < 0.5 {
-            // Spawn on the left or right edge
-            let x = if random_f32() < 0.5 {
-                win.left() - margin
-            } else {
-                win.right() + margin
-            };
-            let y = random_f32() * win.h();
-            (x, y)
+/// The high-score table downward from `top`, with the run at `highlight` in
+/// yellow.
+fn draw_scores(draw: &Draw, scores: &HighScores, highlight: Option<usize>, top: f32) {
+    let row_height = 20.0;
+    if scores.entries().is_empty() {
+        return;
+    }
+    draw.rect()
+        .x_y(0.0, top - row_height * scores.entries().len() as f32 / 2.0)
+        .w_h(520.0, row_height * (scores.entries().len() + 1) as f32)
+        .color(rgba(0.0, 0.0, 0.0, 0.8));
+    for (row, entry) in scores.entries().iter().enumerate() {
+        let line = format!(
+            "{:>2}. {:>6}  {:>6.1}s  seed {:<20}  {}  v{}",
+            row + 1,
+            entry.score,
+            entry.survived,
+            entry.seed,
+            entry.date,
+            entry.version
+        );
+        let color = if Some(row) == highlight { YELLOW } else { GRAY };
+        draw.text(&line)
+            .x_y(0.0, top - row as f32 * row_height)
+            .w(500.0)
+            .left_justify()
+            .color(color)
+            .font_size(14);
+    }
+}
+
+fn draw_health_bar(draw: &Draw, win: Rect, world: &World) {
+    let width = 200.0;
+    let height = 12.0;
+    let x = win.right() - 20.0 - width / 2.0;
+    let y = win.top() - 30.0;
+    let fraction = world.health.hp / world.health.max_hp;
+
+    draw.rect()
+        .x_y(x, y)
+        .w_h(width, height)
+        .color(rgba(1.0, 1.0, 1.0, 0.2));
+    draw.rect()
+        .x_y(x - width * (1.0 - fraction) / 2.0, y)
+        .w_h(width * fraction, height)
+        .color(RED);
+}
+
+/// Fills up as the dash cools down, under the health bar.
+fn draw_dash_bar(draw: &Draw, win: Rect, world: &World) {
+    let width = 200.0;
+    let height = 6.0;
+    let x = win.right() - 20.0 - width / 2.0;
+    let y = win.top() - 46.0;
+    let fraction = 1.0 - world.dash_cooldown / world.tuning.dash_cooldown;
+    let color = if world.dash_cooldown > 0.0 {
+        rgba(0.4, 0.8, 1.0, 0.5)
+    } else {
+        rgba(0.4, 0.8, 1.0, 1.0)
+    };
+
+    draw.rect()
+        .x_y(x, y)
+        .w_h(width, height)
+        .color(rgba(1.0, 1.0, 1.0, 0.2));
+    draw.rect()
+        .x_y(x - width * (1.0 - fraction) / 2.0, y)
+        .w_h(width * fraction, height)
+        .color(color);
+}
+
+/// Level and progress toward the next one, across the top of the window.
+fn draw_xp_bar(draw: &Draw, win: Rect, world: &World) {
+    let height = 6.0;
+    let y = win.top() - height / 2.0;
+    let fraction = (world.xp as f32 / world.xp_to_next_level() as f32).min(1.0);
+
+    draw.rect()
+        .x_y(0.0, y)
+        .w_h(win.w(), height)
+        .color(rgba(1.0, 1.0, 1.0, 0.1));
+    draw.rect()
+        .x_y(win.left() + win.w() * fraction / 2.0, y)
+        .w_h(win.w() * fraction, height)
+        .color(rgba(0.3, 1.0, 0.5, 1.0));
+    draw.text(&format!("lv {}", world.level))
+        .x_y(-win.right() + 50.0, win.top() - 90.0)
+        .color(rgba(0.3, 1.0, 0.5, 1.0))
+        .font_size(18);
+}
+
+/// The upgrades on offer side by side, numbered by the key that takes them.
+fn draw_level_up(draw: &Draw, world: &World) {
+    let card_width = 200.0;
+    let gap = 20.0;
+    let count = world.choices.len() as f32;
+    draw.rect()
+        .x_y(0.0, 0.0)
+        .w_h((card_width + gap) * count + gap, 300.0)
+        .color(rgba(0.0, 0.0, 0.0, 0.8));
+    draw.text(&format!("LEVEL {}", world.level))
+        .x_y(0.0, 110.0)
+        .color(rgba(0.3, 1.0, 0.5, 1.0))
+        .font_size(36)
+        .w(400.0);
+
+    for (slot, index) in world.choices.iter().enumerate() {
+        let upgrade = world.tuning.upgrades.get(*index);
+        let x = (slot as f32 - (count - 1.0) / 2.0) * (card_width + gap);
+        draw.rect()
+            .x_y(x, -10.0)
+            .w_h(card_width, 180.0)
+            .color(rgba(1.0, 1.0, 1.0, 0.1));
+        draw.text(&(slot + 1).to_string())
+            .x_y(x, 50.0)
+            .color(YELLOW)
+            .font_size(28);
+        draw.text(&upgrade.label)
+            .x_y(x, 0.0)
+            .w(card_width - 20.0)
+            .color(WHITE)
+            .font_size(20);
+        let taken = world.taken[*index];
+        let description = if taken > 0 {
+            format!("{}\n(have {taken})", upgrade.description)
         } else {
-            // Spawn on the top or bottom edge
-            let x = random_f32() * win.w();
-            let y = if random_f32() < 0.5 {
-                win.bottom() - margin
-            } else {
-                win.top() + margin
-            };
-            (x, y)
+            upgrade.description.clone()
         };
-        let position = Point2::new(x, y);
-        let radius = random_range(10.0, 20.0);
-        let color = Rgba::new(random_f32(), random_f32(), random_f32(), 1.0);
-        model.enemies.push(Enemy::new(position, radius, color));
-        model.enemy_timer = 0.0;
+        draw.text(&description)
+            .x_y(x, -50.0)
+            .w(card_width - 20.0)
+            .color(GRAY)
+            .font_size(14);
     }
 }
 
-fn despawn_enemies(app: &App, model: &mut Model) {
-    let win = app.window_rect();
-    let margin = 500.0; // Twice the margin used in spawn_enemies
-    let mut i = 0;
-    while i < model.enemies.len() {
-        let x = model.enemies[i].position.x;
-        let y = model.enemies[i].position.y;
-        let radius = model.enemies[i].radius;
-        if x + radius < win.left() - margin
-            || x - radius > win.right() + margin
-            || y + radius < win.bottom() - margin
-            || y - radius > win.top() + margin
-        {
-            model.enemies.remove(i);
-            model.score += 1; // Increase the score
-        } else {
-            i += 1;
-        }
+fn draw_game_over(draw: &Draw, world: &World, restart: Option<&Button>) {
+    draw.rect()
+        .x_y(0.0, 0.0)
+        .w_h(420.0, 240.0)
+        .color(rgba(0.0, 0.0, 0.0, 0.8));
+    draw.text("GAME OVER")
+        .x_y(0.0, 70.0)
+        .color(RED)
+        .font_size(40)
+        .w(400.0);
+
+    let summary = format!(
+        "score {}\nsurvived {:.1}s\nseed {}",
+        world.score, world.elapsed, world.seed
+    );
+    draw.text(&summary)
+        .x_y(0.0, 0.0)
+        .color(WHITE)
+        .font_size(20)
+        .w(400.0);
+
+    if let Some(button) = restart {
+        draw.text(&format!("press {button} to restart"))
+            .x_y(0.0, -80.0)
+            .color(GRAY)
+            .font_size(16)
+            .w(400.0);
     }
 }
@@ -0,0 +1,87 @@
+use nannou::prelude::*;
+use survivor::input::{Action, Bindings, Button, MENU_KEY};
+
+/// Lists every action with its buttons and rebinds the selected one to the
+/// next button pressed.
+#[derive(Default)]
+pub struct ControlsMenu {
+    selected: usize,
+    /// Waiting for the button to bind to the selected action.
+    capturing: bool,
+}
+
+impl ControlsMenu {
+    /// Handles a button pressed while the menu is open. Returns `false` once
+    /// the menu should close.
+    pub fn press(&mut self, button: Button, bindings: &mut Bindings) -> bool {
+        let action = Action::ALL[self.selected];
+        if self.capturing {
+            self.capturing = false;
+            if button != Button::Key(Key::Escape) && button.is_bindable() && !button.is_reserved() {
+                bindings.rebind(action, button);
+            }
+            return true;
+        }
+
+        let count = Action::ALL.len();
+        match button {
+            Button::Key(Key::Up) | Button::WheelUp => {
+                self.selected = (self.selected + count - 1) % count;
+            }
+            Button::Key(Key::Down) | Button::WheelDown => {
+                self.selected = (self.selected + 1) % count;
+            }
+            Button::Key(Key::Return) => self.capturing = true,
+            Button::Key(Key::Back | Key::Delete) => bindings.unbind(action),
+            Button::Key(Key::Escape | MENU_KEY) => return false,
+            _ => {}
+        }
+        true
+    }
+
+    pub fn draw(&self, draw: &Draw, bindings: &Bindings) {
+        let row_height = 28.0;
+        let top = row_height * Action::ALL.len() as f32 / 2.0;
+        draw.rect()
+            .w_h(520.0, top * 2.0 + 140.0)
+            .color(rgba(0.0, 0.0, 0.0, 0.85));
+        draw.text("CONTROLS")
+            .x_y(0.0, top + 40.0)
+            .color(WHITE)
+            .font_size(28);
+
+        for (row, action) in Action::ALL.into_iter().enumerate() {
+            let y = top - row as f32 * row_height;
+            let selected = row == self.selected;
+            let buttons = if selected && self.capturing {
+                "press a button...".to_string()
+            } else {
+                let names: Vec<String> = bindings
+                    .buttons(action)
+                    .iter()
+                    .map(|button| button.to_string())
+                    .collect();
+                names.join(", ")
+            };
+            let color = if selected { YELLOW } else { GRAY };
+            draw.text(action.label())
+                .x_y(-120.0, y)
+                .w(200.0)
+                .left_justify()
+                .color(color)
+                .font_size(18);
+            draw.text(&buttons)
+                .x_y(120.0, y)
+                .w(240.0)
+                .left_justify()
+                .color(color)
+                .font_size(18);
+        }
+
+        draw.text("Up/Down select   Enter rebind   Backspace clear   Esc close")
+            .x_y(0.0, -top - 40.0)
+            .w(500.0)
+            .color(GRAY)
+            .font_size(14);
+    }
+}
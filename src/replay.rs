@@ -0,0 +1,452 @@
+//! Recording runs as per-tick input and playing them back deterministically.
+//!
+//! A replay stores the seed, the [`Tuning`] (as TOML, so new fields don't need
+//! a format change) and every tick's [`Input`]. Every
+//! [`CHECKSUM_INTERVAL`] ticks (and on the final tick) it also stores
+//! [`World::checksum`], so playback can tell exactly when it stopped matching
+//! the recording instead of silently showing a different run.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use nannou::prelude::*;
+
+use crate::config;
+use crate::sim::{Event, FixedTimestep, Input, Tuning, World, TICK};
+
+const MAGIC: &[u8; 4] = b"SVRP";
+const VERSION: u16 = 9;
+
+/// Ticks between stored checksums.
+pub const CHECKSUM_INTERVAL: u64 = 60;
+
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 8.0;
+
+const NO_DRAG: u8 = u8::MAX;
+const HAS_ARENA: u8 = 1 << 0;
+const HAS_CHECKSUM: u8 = 1 << 1;
+const HAS_EXTEND: u8 = 1 << 2;
+const HAS_MOVEMENT: u8 = 1 << 3;
+const HAS_FLICK: u8 = 1 << 4;
+const DASH: u8 = 1 << 5;
+const HAS_UPGRADE: u8 = 1 << 6;
+
+/// Everything recorded about a single tick.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub input: Input,
+    /// New arena size if the window was resized before this tick.
+    pub arena: Option<Vec2>,
+    pub checksum: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub tuning: Tuning,
+    pub arena: Vec2,
+    pub frames: Vec<Frame>,
+}
+
+impl Replay {
+    /// A fresh world in the state the recording started from.
+    pub fn new_world(&self) -> World {
+        World::with_tuning(
+            Rect::from_w_h(self.arena.x, self.arena.y),
+            self.seed,
+            self.tuning.clone(),
+        )
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        let tuning = toml::to_string(&self.tuning).map_err(invalid_data)?;
+        writer.write_all(&(tuning.len() as u32).to_le_bytes())?;
+        writer.write_all(tuning.as_bytes())?;
+        write_f32(writer, self.arena.x)?;
+        write_f32(writer, self.arena.y)?;
+        writer.write_all(&(self.frames.len() as u64).to_le_bytes())?;
+
+        for frame in self.frames.iter() {
+            let mut flags = 0;
+            if frame.arena.is_some() {
+                flags |= HAS_ARENA;
+            }
+            if frame.checksum.is_some() {
+                flags |= HAS_CHECKSUM;
+            }
+            if frame.input.extend != 0 {
+                flags |= HAS_EXTEND;
+            }
+            if frame.input.movement != Vec2::ZERO {
+                flags |= HAS_MOVEMENT;
+            }
+            if frame.input.flick != Vec2::ZERO {
+                flags |= HAS_FLICK;
+            }
+            if frame.input.dash {
+                flags |= DASH;
+            }
+            if frame.input.upgrade.is_some() {
+                flags |= HAS_UPGRADE;
+            }
+            let drag = match frame.input.drag_index {
+                Some(index) => u8::try_from(index)
+                    .ok()
+                    .filter(|index| *index != NO_DRAG)
+                    .ok_or_else(|| invalid_data("drag index too large to record"))?,
+                None => NO_DRAG,
+            };
+            writer.write_all(&[flags, drag])?;
+            write_f32(writer, frame.input.cursor.x)?;
+            write_f32(writer, frame.input.cursor.y)?;
+            if let Some(arena) = frame.arena {
+                write_f32(writer, arena.x)?;
+                write_f32(writer, arena.y)?;
+            }
+            if let Some(checksum) = frame.checksum {
+                writer.write_all(&checksum.to_le_bytes())?;
+            }
+            if frame.input.extend != 0 {
+                writer.write_all(&frame.input.extend.to_le_bytes())?;
+            }
+            if frame.input.movement != Vec2::ZERO {
+                write_f32(writer, frame.input.movement.x)?;
+                write_f32(writer, frame.input.movement.y)?;
+            }
+            if frame.input.flick != Vec2::ZERO {
+                write_f32(writer, frame.input.flick.x)?;
+                write_f32(writer, frame.input.flick.y)?;
+            }
+            if let Some(pick) = frame.input.upgrade {
+                writer.write_all(&[pick])?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a survivor replay"));
+        }
+        let version = u16::from_le_bytes(read_array(reader)?);
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "replay version {version} is not supported (expected {VERSION})"
+            )));
+        }
+
+        let seed = u64::from_le_bytes(read_array(reader)?);
+        let tuning_len = u32::from_le_bytes(read_array(reader)?) as usize;
+        let mut tuning = String::new();
+        reader.take(tuning_len as u64).read_to_string(&mut tuning)?;
+        if tuning.len() != tuning_len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let tuning: Tuning = config::parse(&tuning).map_err(invalid_data)?;
+        let arena = vec2(read_f32(reader)?, read_f32(reader)?);
+        let count = u64::from_le_bytes(read_array(reader)?);
+
+        let mut frames = vec![];
+        for _ in 0..count {
+            let [flags, drag] = read_array(reader)?;
+            let cursor = vec2(read_f32(reader)?, read_f32(reader)?);
+            let arena = if flags & HAS_ARENA != 0 {
+                Some(vec2(read_f32(reader)?, read_f32(reader)?))
+            } else {
+                None
+            };
+            let checksum = if flags & HAS_CHECKSUM != 0 {
+                Some(u64::from_le_bytes(read_array(reader)?))
+            } else {
+                None
+            };
+            let extend = if flags & HAS_EXTEND != 0 {
+                i8::from_le_bytes(read_array(reader)?)
+            } else {
+                0
+            };
+            let movement = if flags & HAS_MOVEMENT != 0 {
+                vec2(read_f32(reader)?, read_f32(reader)?)
+            } else {
+                Vec2::ZERO
+            };
+            let flick = if flags & HAS_FLICK != 0 {
+                vec2(read_f32(reader)?, read_f32(reader)?)
+            } else {
+                Vec2::ZERO
+            };
+            let upgrade = if flags & HAS_UPGRADE != 0 {
+                let [pick] = read_array(reader)?;
+                Some(pick)
+            } else {
+                None
+            };
+            let drag_index = (drag != NO_DRAG).then_some(drag as usize);
+            frames.push(Frame {
+                input: Input {
+                    drag_index,
+                    cursor,
+                    extend,
+                    movement,
+                    flick,
+                    dash: flags & DASH != 0,
+                    upgrade,
+                },
+                arena,
+                checksum,
+            });
+        }
+
+        Ok(Replay {
+            seed,
+            tuning,
+            arena,
+            frames,
+        })
+    }
+}
+
+/// Captures a live run tick by tick.
+pub struct Recorder {
+    replay: Replay,
+    last_arena: Vec2,
+}
+
+impl Recorder {
+    /// Starts recording a world that has not been stepped yet.
+    pub fn new(world: &World) -> Self {
+        let arena = arena_size(world.arena);
+        Recorder {
+            replay: Replay {
+                seed: world.seed,
+                tuning: world.tuning.clone(),
+                arena,
+                frames: vec![],
+            },
+            last_arena: arena,
+        }
+    }
+
+    /// Records the input of the tick `world` has just stepped.
+    pub fn record(&mut self, input: &Input, world: &World) {
+        let size = arena_size(world.arena);
+        let arena = (size != self.last_arena).then_some(size);
+        self.last_arena = size;
+        let checksum = world
+            .tick
+            .is_multiple_of(CHECKSUM_INTERVAL)
+            .then(|| world.checksum());
+        self.replay.frames.push(Frame {
+            input: *input,
+            arena,
+            checksum,
+        });
+    }
+
+    /// Steps `world` by up to `ticks` ticks of `input`, recording each. The
+    /// one-off parts of `input` (extending, dashing, picking an upgrade) only
+    /// go to the first tick. Stops early at a game over, or at an upgrade
+    /// offer unless `input` picks one of its choices, since the world stands
+    /// still then and there's no tick to record.
+    pub fn advance(&mut self, world: &mut World, mut input: Input, ticks: u32) {
+        for _ in 0..ticks {
+            let picks = input
+                .upgrade
+                .is_some_and(|pick| (pick as usize) < world.choices.len());
+            if world.is_game_over() || (world.is_leveling_up() && !picks) {
+                break;
+            }
+            world.step(&input, TICK);
+            self.record(&input, world);
+            input.extend = 0;
+            input.dash = false;
+            input.upgrade = None;
+        }
+    }
+
+    /// Stops recording, stamping the final tick with a checksum.
+    pub fn finish(mut self, world: &World) -> Replay {
+        if let Some(frame) = self.replay.frames.last_mut() {
+            frame.checksum = Some(world.checksum());
+        }
+        self.replay
+    }
+}
+
+/// Playback stopped matching the recording.
+#[derive(Debug)]
+pub struct Divergence {
+    pub tick: u64,
+    pub expected: u64,
+    pub actual: u64,
+    pub score: i32,
+    pub enemies: usize,
+    pub head: Point2,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "replay diverged at tick {}", self.tick)?;
+        writeln!(f, "  expected checksum {:016x}", self.expected)?;
+        writeln!(f, "  actual checksum   {:016x}", self.actual)?;
+        write!(
+            f,
+            "  state: score {}, {} enemies, head at ({:.3}, {:.3})",
+            self.score, self.enemies, self.head.x, self.head.y
+        )
+    }
+}
+
+impl Error for Divergence {}
+
+/// Steps a [`Replay`] through the regular [`World::step`] path, with pause,
+/// seek and variable speed.
+pub struct Player {
+    replay: Replay,
+    world: World,
+    timestep: FixedTimestep,
+    pub paused: bool,
+    speed: f32,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        let world = replay.new_world();
+        Player {
+            replay,
+            world,
+            timestep: FixedTimestep::new(TICK),
+            paused: false,
+            speed: 1.0,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Interpolation factor for rendering, see [`FixedTimestep::alpha`].
+    pub fn alpha(&self) -> f32 {
+        self.timestep.alpha()
+    }
+
+    /// Takes the world's pending events, see [`World::events`].
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.world.events)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.replay.frames.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replay.frames.is_empty()
+    }
+
+    /// Whether playback has run out of frames or reached the game over.
+    pub fn is_finished(&self) -> bool {
+        self.world.tick >= self.len() || self.world.is_game_over()
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the playback rate, clamped to `MIN_SPEED..=MAX_SPEED`.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Plays back `elapsed` real seconds scaled by the current speed.
+    pub fn advance(&mut self, elapsed: f32) -> Result<(), Divergence> {
+        if self.paused {
+            return Ok(());
+        }
+        let ticks = self.timestep.advance(elapsed * self.speed);
+        for _ in 0..ticks {
+            if self.is_finished() {
+                break;
+            }
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Steps exactly one recorded tick and verifies its checksum if present.
+    pub fn step(&mut self) -> Result<(), Divergence> {
+        let Some(frame) = self.replay.frames.get(self.world.tick as usize) else {
+            return Ok(());
+        };
+        if let Some(arena) = frame.arena {
+            self.world.arena = Rect::from_w_h(arena.x, arena.y);
+        }
+        self.world.step(&frame.input, TICK);
+
+        match frame.checksum {
+            Some(expected) if expected != self.world.checksum() => Err(Divergence {
+                tick: self.world.tick,
+                expected,
+                actual: self.world.checksum(),
+                score: self.world.score,
+                enemies: self.world.enemies.len(),
+                head: self.world.rope.points[0],
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Jumps to `tick`, re-simulating from the start when seeking backwards.
+    pub fn seek(&mut self, tick: u64) -> Result<(), Divergence> {
+        let tick = tick.min(self.len());
+        if tick < self.world.tick {
+            self.world = self.replay.new_world();
+        }
+        while self.world.tick < tick && !self.is_finished() {
+            self.step()?;
+        }
+        // Don't replay the effects of everything skipped over
+        self.world.events.clear();
+        Ok(())
+    }
+}
+
+fn arena_size(arena: Rect) -> Vec2 {
+    vec2(arena.w(), arena.h())
+}
+
+fn write_f32(writer: &mut impl Write, value: f32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    Ok(f32::from_le_bytes(read_array(reader)?))
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(error: impl Into<Box<dyn Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
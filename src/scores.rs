@@ -0,0 +1,211 @@
+//! The local high-score table, kept between sessions in the player's data
+//! directory.
+//!
+//! Scores are only ever a nice-to-have: a missing or unreadable file starts a
+//! fresh table rather than stopping the game, and a corrupt one is moved
+//! aside instead of being overwritten, in case the player wants it back.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ConfigError, Validate};
+use crate::sim::World;
+
+/// Runs kept in the table.
+pub const MAX_ENTRIES: usize = 10;
+
+/// Environment variable naming the file to use instead of the default.
+pub const PATH_VAR: &str = "SURVIVOR_SCORES";
+
+const FILE_NAME: &str = "scores.toml";
+
+/// One finished run.
+// Unknown fields are let through so files from newer builds still load
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub score: i32,
+    /// Seconds the run lasted.
+    pub survived: f32,
+    /// Seed to replay the run's spawns with `--seed`.
+    pub seed: u64,
+    /// Day the run ended, as `YYYY-MM-DD` in UTC.
+    pub date: String,
+    /// Version of the game the run was played on.
+    pub version: String,
+}
+
+impl Entry {
+    /// The entry for `world`'s run, ending today on this build.
+    pub fn new(world: &World) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        Entry {
+            score: world.score,
+            survived: world.elapsed,
+            seed: world.seed,
+            date: date(now),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// The best runs so far, highest score first, as in `scores.toml`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default)]
+    entries: Vec<Entry>,
+}
+
+impl HighScores {
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Adds `entry` if it's good enough, returning its place from 0.
+    /// Ties go to the longer run, then to the one already in the table.
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| (entry.score, entry.survived) > (other.score, other.survived))
+            .unwrap_or(self.entries.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+
+    /// Reads the table at `path`, or starts an empty one if there is none
+    /// or it can't be read. A file that is there but garbled is renamed to
+    /// `<path>.bad` first so saving doesn't lose it. The error, if any, is
+    /// returned for the player.
+    pub fn load(path: impl AsRef<Path>) -> (Self, Option<ConfigError>) {
+        let path = path.as_ref();
+        let parsed = match fs::read_to_string(path) {
+            Ok(text) => config::parse::<HighScores>(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return (HighScores::default(), None)
+            }
+            // Not UTF-8, so garbled as well
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => Err(ConfigError::Io(err)),
+            Err(err) => return (HighScores::default(), Some(ConfigError::Io(err))),
+        };
+        match parsed {
+            Ok(mut scores) => {
+                scores.sort();
+                (scores, None)
+            }
+            Err(err) => {
+                let _ = fs::rename(path, backup_path(path));
+                (HighScores::default(), Some(err))
+            }
+        }
+    }
+
+    /// Writes the table as TOML, creating the data directory if needed. The
+    /// file is replaced in one step so a crash mid-write can't corrupt it.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text =
+            toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let partial = path.with_extension("toml.tmp");
+        fs::write(&partial, text)?;
+        fs::rename(&partial, path)
+    }
+
+    /// Keeps a hand-edited file in order and within [`MAX_ENTRIES`].
+    fn sort(&mut self) {
+        self.entries.sort_by(|a, b| {
+            (b.score, b.survived)
+                .partial_cmp(&(a.score, a.survived))
+                .unwrap()
+        });
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+impl Validate for HighScores {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for entry in self.entries.iter() {
+            if !(entry.survived >= 0.0 && entry.survived.is_finite()) {
+                return Err(ConfigError::invalid(format!(
+                    "scores: survived must be 0 or more (got {})",
+                    entry.survived
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the table is kept: `$SURVIVOR_SCORES` if set, or else
+/// `survivor/scores.toml` in the platform's data directory. `None` if
+/// neither can be found, in which case scores aren't kept.
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(PATH_VAR).filter(|path| !path.is_empty()) {
+        return Some(path.into());
+    }
+    data_dir().map(|dir| dir.join("survivor").join(FILE_NAME))
+}
+
+fn data_dir() -> Option<PathBuf> {
+    let var = |name| {
+        env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local/share")))
+    }
+}
+
+/// The first of `<path>.bad`, `<path>.bad.1`, ... that isn't taken.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bad");
+    let mut backup = PathBuf::from(&name);
+    let mut count = 0;
+    while backup.exists() {
+        count += 1;
+        let mut numbered = name.clone();
+        numbered.push(format!(".{count}"));
+        backup = numbered.into();
+    }
+    backup
+}
+
+/// `YYYY-MM-DD` for `seconds` since the Unix epoch, in UTC.
+pub fn date(seconds: u64) -> String {
+    // Howard Hinnant's days-to-civil, for days counted from 1970-01-01
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
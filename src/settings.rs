@@ -0,0 +1,81 @@
+//! Player preferences for the frontend, as opposed to the [`Tuning`] that
+//! shapes a run and is recorded in replays.
+//!
+//! [`Tuning`]: crate::sim::Tuning
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ConfigError, Validate};
+use crate::gamepad::GamepadSettings;
+use crate::input::Bindings;
+
+/// The settings shipped with the game.
+const BUILTIN: &str = include_str!("../assets/settings.toml");
+
+/// Which devices may move the head.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Controls {
+    /// Hold a mouse button to drag the head toward the cursor.
+    Mouse,
+    /// Steer the head with the movement bindings.
+    Keyboard,
+    /// Either, at the same time if wanted.
+    Both,
+}
+
+impl Controls {
+    pub fn mouse(self) -> bool {
+        matches!(self, Controls::Mouse | Controls::Both)
+    }
+
+    pub fn keyboard(self) -> bool {
+        matches!(self, Controls::Keyboard | Controls::Both)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub controls: Controls,
+    /// Left out of files saved before controller support.
+    #[serde(default = "shipped_gamepad")]
+    pub gamepad: GamepadSettings,
+    /// Left out of files saved before rebinding.
+    #[serde(default = "shipped_bindings")]
+    pub bindings: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        config::parse(BUILTIN).expect("built-in settings.toml is valid")
+    }
+}
+
+fn shipped_gamepad() -> GamepadSettings {
+    Settings::default().gamepad
+}
+
+fn shipped_bindings() -> Bindings {
+    Settings::default().bindings
+}
+
+impl Settings {
+    /// Writes the settings as TOML, e.g. after rebinding in game.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text =
+            toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, text)
+    }
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.gamepad.validate()?;
+        self.bindings.validate()
+    }
+}
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+
+use super::rng::Rng;
+use crate::config::{self, ConfigError, Validate};
+
+/// The enemy set shipped with the game, also used by headless runs.
+const BUILTIN: &str = include_str!("../../assets/enemies.toml");
+
+/// How an enemy moves toward the player.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Behaviour {
+    /// Steers straight at the player.
+    Homing,
+    /// Homes in along a path that swings `weave_angle` degrees either side.
+    Swarmer {
+        weave_angle: f32,
+        weave_frequency: f32,
+    },
+    /// Slows to a stop for `windup` seconds, then dashes in a straight line.
+    Charger {
+        windup: f32,
+        dash_speed: f32,
+        dash_time: f32,
+    },
+}
+
+/// A kind of enemy, as described in `assets/enemies.toml`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Archetype {
+    pub name: String,
+    /// Top speed in px/s.
+    pub speed: f32,
+    /// Homing acceleration in px/s².
+    pub acceleration: f32,
+    pub mass: f32,
+    /// Radius range `[min, max]`.
+    pub radius: [f32; 2],
+    pub hp: f32,
+    /// RGB in `0..=1`; each enemy gets a random colour when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[f32; 3]>,
+    /// Points awarded for the kill.
+    pub score: i32,
+    /// Relative likelihood of being picked at spawn time.
+    pub spawn_weight: f32,
+    pub behaviour: Behaviour,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Archetypes {
+    #[serde(rename = "enemy")]
+    pub archetypes: Vec<Archetype>,
+}
+
+impl Default for Archetypes {
+    fn default() -> Self {
+        config::parse(BUILTIN).expect("built-in enemies.toml is valid")
+    }
+}
+
+impl Archetypes {
+    pub fn get(&self, index: usize) -> &Archetype {
+        &self.archetypes[index]
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.archetypes
+            .iter()
+            .position(|archetype| archetype.name == name)
+    }
+
+    /// Picks an archetype index weighted by `spawn_weight`.
+    pub fn choose(&self, rng: &mut Rng) -> usize {
+        let total: f32 = self.archetypes.iter().map(|a| a.spawn_weight).sum();
+        let mut roll = rng.next_f32() * total;
+        for (index, archetype) in self.archetypes.iter().enumerate() {
+            if roll < archetype.spawn_weight {
+                return index;
+            }
+            roll -= archetype.spawn_weight;
+        }
+        // Only reachable through rounding at the very top of the range
+        self.archetypes
+            .iter()
+            .rposition(|archetype| archetype.spawn_weight > 0.0)
+            .unwrap_or_default()
+    }
+}
+
+impl Validate for Archetypes {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.archetypes.is_empty() {
+            return Err(ConfigError::invalid("no [[enemy]] archetypes defined"));
+        }
+        for (index, archetype) in self.archetypes.iter().enumerate() {
+            if self.archetypes[..index]
+                .iter()
+                .any(|other| other.name == archetype.name)
+            {
+                return Err(ConfigError::invalid(format!(
+                    "archetype `{}` is defined twice",
+                    archetype.name
+                )));
+            }
+            archetype.validate()?;
+        }
+        if self.archetypes.iter().all(|a| a.spawn_weight == 0.0) {
+            return Err(ConfigError::invalid(
+                "at least one archetype needs a spawn_weight above 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for Archetype {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| {
+            let name = config::display_name(&self.name);
+            Err(ConfigError::invalid(format!(
+                "archetype `{name}`: {message}"
+            )))
+        };
+
+        if self.name.trim().is_empty() {
+            return invalid("name must not be empty".into());
+        }
+        config::positive("speed", self.speed).or_else(invalid)?;
+        config::positive("acceleration", self.acceleration).or_else(invalid)?;
+        config::positive("mass", self.mass).or_else(invalid)?;
+        config::positive("hp", self.hp).or_else(invalid)?;
+        let [min, max] = self.radius;
+        config::positive("radius", min).or_else(invalid)?;
+        if max < min {
+            return invalid(format!("radius range [{min}, {max}] is backwards"));
+        }
+        if let Some(color) = self.color {
+            if color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                return invalid(format!("color components must be in 0..1 (got {color:?})"));
+            }
+        }
+        if self.score < 0 {
+            return invalid(format!("score must not be negative (got {})", self.score));
+        }
+        if !(self.spawn_weight >= 0.0 && self.spawn_weight.is_finite()) {
+            return invalid(format!(
+                "spawn_weight must be 0 or more (got {})",
+                self.spawn_weight
+            ));
+        }
+
+        match self.behaviour {
+            Behaviour::Homing => {}
+            Behaviour::Swarmer {
+                weave_angle,
+                weave_frequency,
+            } => {
+                if !(0.0..=90.0).contains(&weave_angle) {
+                    return invalid(format!(
+                        "weave_angle must be between 0 and 90 degrees (got {weave_angle})"
+                    ));
+                }
+                config::positive("weave_frequency", weave_frequency).or_else(invalid)?;
+            }
+            Behaviour::Charger {
+                windup,
+                dash_speed,
+                dash_time,
+            } => {
+                config::positive("windup", windup).or_else(invalid)?;
+                config::positive("dash_speed", dash_speed).or_else(invalid)?;
+                config::positive("dash_time", dash_time).or_else(invalid)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use nannou::prelude::*;
+
+use super::enemy::Enemy;
+use super::grid::SpatialHash;
+use super::rope::Rope;
+
+/// A rope point or segment touching an enemy during one substep.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub enemy: usize,
+    pub position: Point2,
+    /// Unit vector from the rope toward the enemy.
+    pub normal: Vec2,
+    /// Rope velocity relative to the enemy, in px per substep.
+    pub relative_velocity: Vec2,
+}
+
+/// A rope feature an enemy may be touching. Sweeps order first so they see
+/// each enemy before any segment pushes it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Feature {
+    Sweep(usize),
+    Segment(usize),
+}
+
+/// Resolves overlaps between the rope and enemies, and between enemies, for
+/// one substep. With `continuous` set, rope points that passed through an
+/// enemy during the substep also register a hit.
+///
+/// Candidate pairs come from a [`SpatialHash`] of the enemies, giving the same
+/// result as [`check_collisions_brute_force`] without testing every pair.
+pub fn check_collisions(
+    rope: &mut Rope,
+    enemies: &mut [Enemy],
+    substeps: i32,
+    continuous: bool,
+) -> Vec<Hit> {
+    let max_radius = enemies.iter().map(|e| e.radius).fold(0.0, f32::max);
+    let max_step = enemies
+        .iter()
+        .map(|e| e.velocity().length())
+        .fold(0.0, f32::max);
+    // Enemies are bucketed where they start, so reach a little further to cover
+    // this substep's motion and the pushes they may get before being looked up
+    let margin = vec2(1.0, 1.0) * (max_radius * 2.0 + rope.thickness() + max_step);
+
+    let mut grid = SpatialHash::new(max_radius * 2.0);
+    for (index, enemy) in enemies.iter().enumerate() {
+        grid.insert(index, enemy.position);
+    }
+    resolve(rope, enemies, substeps, continuous, |min, max, out| {
+        grid.query(min - margin, max + margin, out)
+    })
+}
+
+/// [`check_collisions`] testing every pair, as a reference for the broadphase.
+pub fn check_collisions_brute_force(
+    rope: &mut Rope,
+    enemies: &mut [Enemy],
+    substeps: i32,
+    continuous: bool,
+) -> Vec<Hit> {
+    let count = enemies.len();
+    resolve(rope, enemies, substeps, continuous, |_, _, out| {
+        out.extend(0..count)
+    })
+}
+
+/// Runs the narrowphase over the enemies `near` reports for each box, in the
+/// same order a plain loop over every pair would.
+fn resolve(
+    rope: &mut Rope,
+    enemies: &mut [Enemy],
+    substeps: i32,
+    continuous: bool,
+    near: impl Fn(Point2, Point2, &mut Vec<usize>),
+) -> Vec<Hit> {
+    let mut hits = vec![];
+    let mut nearby = vec![];
+    let padding = vec2(1.0, 1.0) * rope.thickness();
+
+    let mut pairs = vec![];
+    if continuous {
+        for i in 0..rope.points.len() {
+            let (a, b) = (rope.prev_points[i], rope.points[i]);
+            nearby.clear();
+            near(a.min(b) - padding, a.max(b) + padding, &mut nearby);
+            pairs.extend(nearby.iter().map(|&enemy| (enemy, Feature::Sweep(i))));
+        }
+    }
+    for i in 0..rope.links.len() {
+        if rope.links[i].is_none() {
+            continue;
+        }
+        let (a, b) = (rope.points[i], rope.points[i + 1]);
+        nearby.clear();
+        near(a.min(b) - padding, a.max(b) + padding, &mut nearby);
+        pairs.extend(nearby.iter().map(|&enemy| (enemy, Feature::Segment(i))));
+    }
+    pairs.sort_unstable();
+
+    for (index, feature) in pairs {
+        let enemy = &mut enemies[index];
+        let hit = match feature {
+            Feature::Sweep(i) => sweep_point(rope, i, index, enemy),
+            Feature::Segment(i) => collide_segment(rope, i, index, enemy),
+        };
+        hits.extend(hit);
+    }
+
+    for i in 0..enemies.len() {
+        let extent = vec2(1.0, 1.0) * enemies[i].radius;
+        nearby.clear();
+        near(
+            enemies[i].position - extent,
+            enemies[i].position + extent,
+            &mut nearby,
+        );
+        for &j in nearby.iter().filter(|&&j| j > i) {
+            let distance = enemies[i].position.distance(enemies[j].position);
+            if distance < enemies[i].radius + enemies[j].radius {
+                // Push both apart, the lighter one further
+                let direction = (enemies[i].position - enemies[j].position).normalize();
+                let overlap = (enemies[i].radius + enemies[j].radius - distance) / substeps as f32;
+                let (weight_i, weight_j) = (1.0 / enemies[i].mass, 1.0 / enemies[j].mass);
+                let share = overlap / (weight_i + weight_j);
+                enemies[i].position += direction * share * weight_i;
+                enemies[j].position -= direction * share * weight_j;
+            }
+        }
+    }
+
+    hits
+}
+
+/// Resolves enemy `index` against the capsule swept by segment `i` of the rope.
+///
+/// The correction is shared by mass: the contact point's inverse mass is its
+/// endpoints' weighted by barycentric position, and each endpoint moves in
+/// proportion to its weight and inverse mass.
+fn collide_segment(rope: &mut Rope, i: usize, index: usize, enemy: &mut Enemy) -> Option<Hit> {
+    let a = rope.points[i];
+    let b = rope.points[i + 1];
+    let t = closest_parameter(a, b, enemy.position);
+    let contact = a.lerp(b, t);
+
+    let offset = enemy.position - contact;
+    let distance = offset.length();
+    let reach = enemy.radius + rope.thickness();
+    if distance >= reach {
+        return None;
+    }
+    let normal = if distance > f32::EPSILON {
+        offset / distance
+    } else {
+        // Dead centre on the segment: push out sideways
+        (b - a).perp().try_normalize().unwrap_or(Vec2::Y)
+    };
+
+    let segment_velocity = (a - rope.prev_points[i]).lerp(b - rope.prev_points[i + 1], t);
+    let hit = Hit {
+        enemy: index,
+        position: contact,
+        normal,
+        relative_velocity: segment_velocity - enemy.velocity(),
+    };
+
+    let (weight_a, weight_b) = (
+        (1.0 - t) * rope.inverse_masses[i],
+        t * rope.inverse_masses[i + 1],
+    );
+    let contact_weight = (1.0 - t) * weight_a + t * weight_b;
+    let enemy_weight = 1.0 / enemy.mass;
+    let correction = normal * (reach - distance) / (contact_weight + enemy_weight);
+    enemy.position += correction * enemy_weight;
+    rope.points[i] -= correction * weight_a;
+    rope.points[i + 1] -= correction * weight_b;
+
+    Some(hit)
+}
+
+/// Sweeps rope point `i` against enemy `index` over the last substep and
+/// reports a hit if it entered and left the enemy's reach without ending up
+/// inside it, which the discrete pass would miss. Positions are left alone;
+/// the hit's knockback is the response.
+fn sweep_point(rope: &Rope, i: usize, index: usize, enemy: &Enemy) -> Option<Hit> {
+    let reach = enemy.radius + rope.thickness();
+    // Work relative to the enemy so both motions are accounted for
+    let start = rope.prev_points[i] - enemy.prev_position;
+    let end = rope.points[i] - enemy.position;
+    if start.length() < reach || end.length() < reach {
+        return None;
+    }
+
+    let motion = end - start;
+    let a = motion.length_squared();
+    let b = 2.0 * start.dot(motion);
+    let c = start.length_squared() - reach * reach;
+    let discriminant = b * b - 4.0 * a * c;
+    if a <= f32::EPSILON || discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    if !(0.0..=1.0).contains(&t) {
+        return None;
+    }
+
+    let offset = start + motion * t;
+    Some(Hit {
+        enemy: index,
+        position: rope.prev_points[i].lerp(rope.points[i], t),
+        normal: -offset.normalize_or_zero(),
+        relative_velocity: (rope.points[i] - rope.prev_points[i]) - enemy.velocity(),
+    })
+}
+
+/// How far along `a..b` the point closest to `p` lies, in `0..=1`.
+pub(super) fn closest_parameter(a: Point2, b: Point2, p: Point2) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared <= f32::EPSILON {
+        return 0.0;
+    }
+    ((p - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+}
+
+/// Whether any enemy overlaps the rope head.
+pub fn head_contact(rope: &Rope, enemies: &[Enemy]) -> bool {
+    let head = rope.points[0];
+    let head_radius = rope.head_radius();
+    enemies
+        .iter()
+        .any(|enemy| enemy.position.distance(head) < enemy.radius + head_radius)
+}
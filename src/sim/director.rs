@@ -0,0 +1,328 @@
+use std::collections::BTreeMap;
+use std::f32::consts::TAU;
+
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::archetype::Archetypes;
+use super::rng::Rng;
+use crate::config::{self, ConfigError, Validate};
+
+/// The wave schedule shipped with the game, also used by headless runs.
+const BUILTIN: &str = include_str!("../../assets/waves.toml");
+
+/// Distance outside the arena that enemies appear at.
+const SPAWN_MARGIN: f32 = 30.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Edge {
+    pub const ALL: [Edge; 4] = [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom];
+}
+
+/// How the members of one group are placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Formation {
+    /// Independently along the chosen edge.
+    #[default]
+    Scatter,
+    /// Bunched up around one point on the edge.
+    Cluster,
+    /// Evenly spaced in a line along the edge.
+    Line,
+    /// Evenly around the whole arena, ignoring `edges`.
+    Ring,
+}
+
+/// One stream of spawns active between `start` and `end` seconds into a run.
+/// Waves may overlap; each keeps its own spawn timer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Wave {
+    #[serde(default)]
+    pub name: String,
+    pub start: f32,
+    pub end: f32,
+    /// Seconds between groups, ramping linearly from the first value at
+    /// `start` to the second at `end`.
+    pub interval: [f32; 2],
+    /// Enemies per group, picked uniformly from `[min, max]`.
+    pub group: [u32; 2],
+    #[serde(default)]
+    pub formation: Formation,
+    /// Edges groups may come from; all four when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edges: Vec<Edge>,
+    /// Archetype name to relative weight, one pick per group. Uses each
+    /// archetype's own `spawn_weight` when empty.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mix: BTreeMap<String, f32>,
+}
+
+impl Wave {
+    /// How far through the wave `time` is, clamped to `0..=1`.
+    fn progress(&self, time: f32) -> f32 {
+        ((time - self.start) / (self.end - self.start)).clamp(0.0, 1.0)
+    }
+
+    pub fn interval_at(&self, time: f32) -> f32 {
+        let [from, to] = self.interval;
+        from + (to - from) * self.progress(time)
+    }
+
+    fn pick_archetype(&self, archetypes: &Archetypes, rng: &mut Rng) -> usize {
+        if self.mix.is_empty() {
+            return archetypes.choose(rng);
+        }
+        let total: f32 = self.mix.values().sum();
+        let mut roll = rng.next_f32() * total;
+        let mut chosen = None;
+        for (name, weight) in self.mix.iter() {
+            if *weight > 0.0 {
+                chosen = Some(name);
+            }
+            if roll < *weight {
+                break;
+            }
+            roll -= weight;
+        }
+        chosen
+            .and_then(|name| archetypes.find(name))
+            .expect("wave mix is validated against the archetypes")
+    }
+}
+
+/// The difficulty curve: when, where and how densely enemies arrive.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Waves {
+    /// Once every wave has ended, keep the ones that ended last running at
+    /// their final density instead of going quiet.
+    #[serde(default = "overtime_default")]
+    pub overtime: bool,
+    #[serde(rename = "wave")]
+    pub waves: Vec<Wave>,
+}
+
+fn overtime_default() -> bool {
+    true
+}
+
+impl Default for Waves {
+    fn default() -> Self {
+        config::parse(BUILTIN).expect("built-in waves.toml is valid")
+    }
+}
+
+impl Waves {
+    fn last_end(&self) -> f32 {
+        self.waves.iter().map(|wave| wave.end).fold(0.0, f32::max)
+    }
+
+    pub fn is_active(&self, wave: &Wave, time: f32) -> bool {
+        (wave.start..wave.end).contains(&time)
+            || (self.overtime && time >= wave.end && wave.end == self.last_end())
+    }
+
+    /// Expected enemies per second at `time`, summed over active waves.
+    pub fn spawn_rate(&self, time: f32) -> f32 {
+        self.waves
+            .iter()
+            .filter(|wave| self.is_active(wave, time))
+            .map(|wave| {
+                let [min, max] = wave.group;
+                (min + max) as f32 / 2.0 / wave.interval_at(time)
+            })
+            .sum()
+    }
+
+    /// The most recently started wave that is still running.
+    pub fn current(&self, time: f32) -> Option<&Wave> {
+        self.waves
+            .iter()
+            .filter(|wave| self.is_active(wave, time))
+            .max_by(|a, b| a.start.total_cmp(&b.start))
+    }
+
+    /// Checks that every name in a wave mix refers to a known archetype.
+    pub fn validate_against(&self, archetypes: &Archetypes) -> Result<(), ConfigError> {
+        for wave in self.waves.iter() {
+            for name in wave.mix.keys() {
+                if archetypes.find(name).is_none() {
+                    return Err(ConfigError::invalid(format!(
+                        "wave `{}`: unknown archetype `{name}` in mix",
+                        wave.name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Validate for Waves {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.waves.is_empty() {
+            return Err(ConfigError::invalid("no [[wave]] entries defined"));
+        }
+        for wave in self.waves.iter() {
+            let invalid = |message: String| {
+                Err(ConfigError::invalid(format!(
+                    "wave `{}`: {message}",
+                    wave.name
+                )))
+            };
+            if !(wave.start >= 0.0 && wave.end > wave.start) {
+                return invalid(format!(
+                    "needs 0 <= start < end (got start {}, end {})",
+                    wave.start, wave.end
+                ));
+            }
+            if wave.interval.iter().any(|i| !(*i > 0.0 && i.is_finite())) {
+                return invalid(format!(
+                    "interval must be positive (got {:?})",
+                    wave.interval
+                ));
+            }
+            let [min, max] = wave.group;
+            if min == 0 || max < min {
+                return invalid(format!(
+                    "group must be [min, max] with 1 <= min <= max (got [{min}, {max}])"
+                ));
+            }
+            if wave.mix.values().any(|w| !(*w >= 0.0 && w.is_finite())) {
+                return invalid("mix weights must be 0 or more".into());
+            }
+            if !wave.mix.is_empty() && wave.mix.values().all(|w| *w == 0.0) {
+                return invalid("mix needs at least one weight above 0".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An enemy the director wants placed this tick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spawn {
+    pub archetype: usize,
+    pub position: Point2,
+}
+
+/// Runs a [`Waves`] schedule, deciding what to spawn each tick.
+#[derive(Clone, Debug)]
+pub struct Director {
+    /// Seconds until each wave's next group.
+    until_next: Vec<f32>,
+}
+
+impl Director {
+    pub fn new(waves: &Waves) -> Self {
+        Director {
+            until_next: waves
+                .waves
+                .iter()
+                .map(|wave| wave.interval_at(wave.start))
+                .collect(),
+        }
+    }
+
+    /// Advances to `time` (seconds into the run, after this tick's `dt`) and
+    /// returns the enemies due.
+    pub fn update(
+        &mut self,
+        waves: &Waves,
+        archetypes: &Archetypes,
+        arena: Rect,
+        time: f32,
+        dt: f32,
+        rng: &mut Rng,
+    ) -> Vec<Spawn> {
+        let mut spawns = vec![];
+        for (wave, until_next) in waves.waves.iter().zip(self.until_next.iter_mut()) {
+            if !waves.is_active(wave, time) {
+                continue;
+            }
+            *until_next -= dt;
+            while *until_next <= 0.0 {
+                spawn_group(wave, archetypes, arena, rng, &mut spawns);
+                *until_next += wave.interval_at(time);
+            }
+        }
+        spawns
+    }
+
+    /// Timer state, so the world checksum covers it.
+    pub fn timers(&self) -> &[f32] {
+        &self.until_next
+    }
+}
+
+fn spawn_group(
+    wave: &Wave,
+    archetypes: &Archetypes,
+    arena: Rect,
+    rng: &mut Rng,
+    spawns: &mut Vec<Spawn>,
+) {
+    let archetype = wave.pick_archetype(archetypes, rng);
+    let [min, max] = wave.group;
+    let count = min + (rng.next_u64() % (max - min + 1) as u64) as u32;
+    let edges = if wave.edges.is_empty() {
+        &Edge::ALL[..]
+    } else {
+        &wave.edges[..]
+    };
+    let edge = edges[(rng.next_u64() % edges.len() as u64) as usize];
+    let spacing = archetypes.get(archetype).radius[1] * 2.5;
+
+    let anchor = rng.next_f32();
+    for member in 0..count {
+        let position = match wave.formation {
+            Formation::Scatter => edge_point(arena, edge, rng.next_f32(), 0.0),
+            Formation::Cluster => {
+                let jitter = vec2(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0)) * spacing;
+                let outward = rng.next_f32() * spacing;
+                edge_point(arena, edge, anchor, outward) + jitter
+            }
+            Formation::Line => {
+                let along = (member as f32 - (count - 1) as f32 / 2.0) * spacing;
+                let length = match edge {
+                    Edge::Left | Edge::Right => arena.h(),
+                    Edge::Top | Edge::Bottom => arena.w(),
+                };
+                edge_point(arena, edge, anchor + along / length, 0.0)
+            }
+            Formation::Ring => {
+                let radius = arena.w().hypot(arena.h()) / 2.0 + SPAWN_MARGIN;
+                let angle = (anchor + member as f32 / count as f32) * TAU;
+                arena.xy() + vec2(angle.cos(), angle.sin()) * radius
+            }
+        };
+        spawns.push(Spawn {
+            archetype,
+            position,
+        });
+    }
+}
+
+/// A point just outside `edge`, `t` of the way along it and pushed a further
+/// `outward` away from the arena.
+fn edge_point(arena: Rect, edge: Edge, t: f32, outward: f32) -> Point2 {
+    let offset = SPAWN_MARGIN + outward;
+    let x = arena.left() + arena.w() * t;
+    let y = arena.bottom() + arena.h() * t;
+    match edge {
+        Edge::Left => pt2(arena.left() - offset, y),
+        Edge::Right => pt2(arena.right() + offset, y),
+        Edge::Top => pt2(x, arena.top() + offset),
+        Edge::Bottom => pt2(x, arena.bottom() - offset),
+    }
+}
@@ -0,0 +1,169 @@
+use std::f32::consts::TAU;
+
+use nannou::prelude::*;
+
+use super::archetype::{Archetype, Behaviour};
+
+pub struct Enemy {
+    pub position: Point2,
+    pub prev_position: Point2,
+    /// Position at the start of the current tick, for render interpolation.
+    pub tick_position: Point2,
+    /// Top speed in px/s.
+    pub speed: f32,
+    /// Homing acceleration in px/s².
+    pub acceleration: f32,
+    pub mass: f32,
+    pub radius: f32,
+    pub color: Rgba,
+    pub hp: f32,
+    pub max_hp: f32,
+    /// Points awarded for the kill.
+    pub score: i32,
+    pub behaviour: Behaviour,
+    /// Swarmer weave phase in cycles, or seconds into a charger's windup/dash.
+    pub behaviour_timer: f32,
+    /// Direction of a charger's current dash.
+    pub dash_direction: Option<Vec2>,
+    /// Seconds before the rope can damage this enemy again.
+    pub hit_cooldown: f32,
+    /// Whether the rope is looped around it this tick.
+    pub constricted: bool,
+    /// Fraction of its usual speed it can move at, lowered while constricted.
+    pub slow: f32,
+}
+
+impl Enemy {
+    /// A plain homing enemy.
+    pub fn new(position: Point2, radius: f32, color: Rgba, hp: f32) -> Self {
+        Enemy {
+            position,
+            prev_position: position,
+            tick_position: position,
+            speed: 140.0,
+            acceleration: 180.0,
+            mass: 1.0,
+            radius,
+            color,
+            hp,
+            max_hp: hp,
+            score: 1,
+            behaviour: Behaviour::Homing,
+            behaviour_timer: 0.0,
+            dash_direction: None,
+            hit_cooldown: 0.0,
+            constricted: false,
+            slow: 1.0,
+        }
+    }
+
+    /// An enemy of the given archetype. `phase` offsets swarmer weaving so a
+    /// group doesn't move in lockstep.
+    pub fn from_archetype(
+        archetype: &Archetype,
+        position: Point2,
+        radius: f32,
+        color: Rgba,
+        phase: f32,
+    ) -> Self {
+        Enemy {
+            speed: archetype.speed,
+            acceleration: archetype.acceleration,
+            mass: archetype.mass,
+            score: archetype.score,
+            behaviour: archetype.behaviour,
+            behaviour_timer: match archetype.behaviour {
+                Behaviour::Swarmer { .. } => phase,
+                _ => 0.0,
+            },
+            ..Enemy::new(position, radius, color, archetype.hp)
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.hp <= 0.0
+    }
+
+    /// Displacement over the last substep.
+    pub fn velocity(&self) -> Vec2 {
+        self.position - self.prev_position
+    }
+
+    /// Adds `impulse` (px per substep, for unit mass) to the Verlet velocity.
+    pub fn knock_back(&mut self, impulse: Vec2) {
+        self.prev_position -= impulse / self.mass;
+    }
+
+    pub fn begin_tick(&mut self) {
+        self.tick_position = self.position;
+    }
+
+    pub fn interpolated_position(&self, alpha: f32) -> Point2 {
+        self.tick_position.lerp(self.position, alpha)
+    }
+
+    pub fn update(&mut self, target: Point2, delta_time: f32) {
+        let current = self.position;
+        let prev = self.prev_position;
+        let mut velocity = current - prev;
+        self.prev_position = current;
+
+        // Move towards the target (first point of the rope)
+        let to_target = (target - current).normalize_or_zero();
+        let mut top_speed = self.speed * self.slow;
+        let direction = match self.behaviour {
+            Behaviour::Homing => to_target,
+            Behaviour::Swarmer {
+                weave_angle,
+                weave_frequency,
+            } => {
+                self.behaviour_timer =
+                    (self.behaviour_timer + weave_frequency * delta_time).fract();
+                let angle = weave_angle.to_radians() * (self.behaviour_timer * TAU).sin();
+                let (sin, cos) = angle.sin_cos();
+                vec2(
+                    to_target.x * cos - to_target.y * sin,
+                    to_target.x * sin + to_target.y * cos,
+                )
+            }
+            Behaviour::Charger {
+                windup,
+                dash_speed,
+                dash_time,
+            } => {
+                self.behaviour_timer += delta_time;
+                match self.dash_direction {
+                    Some(dash) if self.behaviour_timer < dash_time => {
+                        top_speed = dash_speed * self.slow;
+                        velocity = dash * top_speed * delta_time;
+                        Vec2::ZERO
+                    }
+                    Some(_) => {
+                        self.dash_direction = None;
+                        self.behaviour_timer = 0.0;
+                        Vec2::ZERO
+                    }
+                    None if self.behaviour_timer >= windup => {
+                        self.dash_direction = Some(to_target);
+                        self.behaviour_timer = 0.0;
+                        Vec2::ZERO
+                    }
+                    None => {
+                        // Brake while winding up, telegraphing the dash
+                        velocity *= 0.98;
+                        Vec2::ZERO
+                    }
+                }
+            }
+        };
+
+        velocity += direction * self.acceleration * delta_time * delta_time;
+        let max_step = top_speed * delta_time;
+        let step = velocity.length();
+        if step > max_step {
+            // Bleed off excess speed (from knockback) gradually rather than clamping it away
+            velocity *= (max_step / step).max(0.98);
+        }
+        self.position = current + velocity;
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+use nannou::prelude::*;
+
+/// Buckets indices by position on a uniform grid so nearby items can be found
+/// without testing every pair.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>, BuildHasherDefault<CellHasher>>,
+}
+
+impl SpatialHash {
+    /// `cell_size` should be around the diameter of the largest item.
+    pub fn new(cell_size: f32) -> Self {
+        SpatialHash {
+            cell_size: cell_size.max(1.0),
+            cells: HashMap::default(),
+        }
+    }
+
+    fn cell(&self, position: Point2) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn insert(&mut self, index: usize, position: Point2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(index);
+    }
+
+    /// Appends the indices inserted anywhere in the cells overlapping
+    /// `min..max` to `out`, in ascending order. May include items just
+    /// outside the box.
+    pub fn query(&self, min: Point2, max: Point2, out: &mut Vec<usize>) {
+        let start = out.len();
+        let (left, bottom) = self.cell(min);
+        let (right, top) = self.cell(max);
+        for x in left..=right {
+            for y in bottom..=top {
+                if let Some(indices) = self.cells.get(&(x, y)) {
+                    out.extend_from_slice(indices);
+                }
+            }
+        }
+        // Callers resolve contacts in index order, as a plain loop would
+        out[start..].sort_unstable();
+    }
+}
+
+/// Multiply-rotate hash for cell coordinates. SipHash's DoS resistance isn't
+/// needed here and it dominated query time.
+#[derive(Default)]
+struct CellHasher(u64);
+
+impl Hasher for CellHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_u64(*byte as u64);
+        }
+    }
+
+    fn write_i32(&mut self, n: i32) {
+        self.write_u64(n as u32 as u64);
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
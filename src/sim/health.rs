@@ -0,0 +1,49 @@
+/// Hit points of the rope head, which is the player.
+#[derive(Clone, Debug)]
+pub struct Health {
+    pub hp: f32,
+    pub max_hp: f32,
+    /// Seconds left before the head can be hurt again.
+    pub invulnerable_for: f32,
+}
+
+impl Health {
+    pub fn new(max_hp: f32) -> Self {
+        Health {
+            hp: max_hp,
+            max_hp,
+            invulnerable_for: 0.0,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.hp <= 0.0
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_for > 0.0
+    }
+
+    /// Applies `amount` damage unless still invulnerable from the last hit.
+    /// Returns whether the hit landed.
+    pub fn hit(&mut self, amount: f32, invulnerability: f32) -> bool {
+        if self.is_invulnerable() || self.is_dead() {
+            return false;
+        }
+        self.hp = (self.hp - amount).max(0.0);
+        self.invulnerable_for = invulnerability;
+        true
+    }
+
+    /// Restores up to `amount` hit points, never past `max_hp` and never once
+    /// dead.
+    pub fn heal(&mut self, amount: f32) {
+        if !self.is_dead() {
+            self.hp = (self.hp + amount).min(self.max_hp);
+        }
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.invulnerable_for = (self.invulnerable_for - dt).max(0.0);
+    }
+}
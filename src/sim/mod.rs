@@ -0,0 +1,721 @@
+//! Headless world state and the per-frame `step` that advances it.
+
+mod archetype;
+mod collision;
+mod director;
+mod enemy;
+mod grid;
+mod health;
+mod orb;
+mod rng;
+mod rope;
+mod timestep;
+mod upgrade;
+
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ConfigError, Validate};
+use collision::closest_parameter;
+
+pub use archetype::{Archetype, Archetypes, Behaviour};
+pub use collision::{check_collisions, check_collisions_brute_force, head_contact, Hit};
+pub use director::{Director, Edge, Formation, Spawn, Wave, Waves};
+pub use enemy::Enemy;
+pub use grid::SpatialHash;
+pub use health::Health;
+pub use orb::Orb;
+pub use rng::Rng;
+pub use rope::{Rope, RopeParams, Solver};
+pub use timestep::FixedTimestep;
+pub use upgrade::{Boosts, Effect, Upgrade, Upgrades};
+
+/// Length of one simulation tick in seconds.
+pub const TICK: f32 = 1.0 / 60.0;
+
+/// Player input sampled by the frontend once per frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Input {
+    /// Index of the rope point being dragged, if any. Ignored past the end
+    /// of the rope, as a hand-edited replay may ask for.
+    pub drag_index: Option<usize>,
+    /// Cursor position in arena coordinates.
+    pub cursor: Point2,
+    /// Segments to add at the head this tick, or take away when negative.
+    pub extend: i8,
+    /// Direction to steer the head in, with a length of at most 1.
+    pub movement: Vec2,
+    /// Direction to flick the rope tip in, with a length of at most 1.
+    pub flick: Vec2,
+    /// Dash this tick, if the cooldown allows.
+    pub dash: bool,
+    /// Which of the offered [`World::choices`] to take, by its place there.
+    pub upgrade: Option<u8>,
+}
+
+/// Something that happened during a step which the frontend may want to show.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// The rope hit an enemy hard enough to hurt it.
+    Damage { position: Point2, amount: f32 },
+    /// A rope segment stretched too far and broke.
+    Tear { position: Point2 },
+    /// The head dashed from here.
+    Dash { position: Point2 },
+    /// An enemy ran out of hit points.
+    Kill {
+        position: Point2,
+        radius: f32,
+        color: Rgba,
+    },
+}
+
+/// Gameplay constants that shape a run; recorded in replays alongside the seed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tuning {
+    /// How quickly a dragged point follows the cursor, in 1/s.
+    pub drag_rate: f32,
+    /// Top speed of the head when steered with [`Input::movement`], px/s.
+    pub head_speed: f32,
+    /// How quickly the steered head speeds up and slows down, px/s².
+    pub head_acceleration: f32,
+    /// Acceleration of the rope tip at full [`Input::flick`], px/s².
+    pub flick_acceleration: f32,
+    /// Speed added to the head by a dash, px/s. Steering slows it back down
+    /// at `head_acceleration`.
+    pub dash_speed: f32,
+    /// Seconds between dashes.
+    pub dash_cooldown: f32,
+    /// Seconds the head can't be hurt after dashing.
+    pub dash_invulnerability: f32,
+    /// Hit points the head starts a run with.
+    pub max_hp: f32,
+    /// Hit points lost when an enemy touches the head.
+    pub contact_damage: f32,
+    /// Seconds the head can't be hurt after taking a hit.
+    pub invulnerability: f32,
+    /// Damage dealt per px/s of rope speed relative to the enemy.
+    pub whip_damage: f32,
+    /// Relative speed in px/s below which the rope only pushes.
+    pub min_hit_speed: f32,
+    /// Fraction of the rope's relative velocity passed on to a hit enemy.
+    pub knockback: f32,
+    /// Seconds an enemy is immune after being hit, so one swing counts once.
+    pub hit_cooldown: f32,
+    /// Sweep rope points through each substep so fast swings can't tunnel
+    /// through enemies.
+    pub continuous_collision: bool,
+    /// Turns the rope must make around an enemy's centre to constrict it; 1
+    /// is a full loop. A little under 1 lets a loop count before its ends
+    /// meet.
+    pub constrict_turns: f32,
+    /// Hit points per second lost by a constricted enemy.
+    pub squeeze_damage: f32,
+    /// Fraction of a constricted enemy's speed taken away, in `0..=1`.
+    pub squeeze_slow: f32,
+    /// Distance in px within which XP orbs are drawn to the head.
+    pub orb_magnet_radius: f32,
+    /// How hard orbs are pulled in at the edge of the magnet, px/s².
+    pub orb_acceleration: f32,
+    /// XP needed to reach level 2.
+    pub xp_base: u32,
+    /// How much more XP each level needs than the one before.
+    pub xp_growth: f32,
+    /// Upgrades offered at each level-up.
+    pub upgrade_choices: u32,
+    pub rope: RopeParams,
+    pub archetypes: Archetypes,
+    pub waves: Waves,
+    pub upgrades: Upgrades,
+}
+
+// Matches `assets/tuning.toml`, which the game loads. Kept in code rather than
+// parsed from it so replays recorded before a field existed still fill it in.
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning {
+            drag_rate: 18.5,
+            head_speed: 300.0,
+            head_acceleration: 2400.0,
+            flick_acceleration: 6000.0,
+            dash_speed: 900.0,
+            dash_cooldown: 1.5,
+            dash_invulnerability: 0.25,
+            max_hp: 5.0,
+            contact_damage: 1.0,
+            invulnerability: 1.0,
+            whip_damage: 0.02,
+            min_hit_speed: 150.0,
+            knockback: 0.5,
+            hit_cooldown: 0.2,
+            continuous_collision: true,
+            constrict_turns: 0.9,
+            squeeze_damage: 20.0,
+            squeeze_slow: 0.7,
+            orb_magnet_radius: 150.0,
+            orb_acceleration: 1500.0,
+            xp_base: 5,
+            xp_growth: 1.3,
+            upgrade_choices: 3,
+            rope: RopeParams::default(),
+            archetypes: Archetypes::default(),
+            waves: Waves::default(),
+            upgrades: Upgrades::default(),
+        }
+    }
+}
+
+impl Validate for Tuning {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| Err(ConfigError::invalid(message));
+        config::positive("drag_rate", self.drag_rate).or_else(invalid)?;
+        config::positive("head_speed", self.head_speed).or_else(invalid)?;
+        config::positive("head_acceleration", self.head_acceleration).or_else(invalid)?;
+        config::positive("flick_acceleration", self.flick_acceleration).or_else(invalid)?;
+        config::positive("dash_speed", self.dash_speed).or_else(invalid)?;
+        config::positive("dash_cooldown", self.dash_cooldown).or_else(invalid)?;
+        config::non_negative("dash_invulnerability", self.dash_invulnerability).or_else(invalid)?;
+        config::positive("max_hp", self.max_hp).or_else(invalid)?;
+        config::non_negative("contact_damage", self.contact_damage).or_else(invalid)?;
+        config::non_negative("invulnerability", self.invulnerability).or_else(invalid)?;
+        config::non_negative("whip_damage", self.whip_damage).or_else(invalid)?;
+        config::non_negative("min_hit_speed", self.min_hit_speed).or_else(invalid)?;
+        config::non_negative("knockback", self.knockback).or_else(invalid)?;
+        config::non_negative("hit_cooldown", self.hit_cooldown).or_else(invalid)?;
+        config::positive("constrict_turns", self.constrict_turns).or_else(invalid)?;
+        config::positive("orb_magnet_radius", self.orb_magnet_radius).or_else(invalid)?;
+        config::positive("orb_acceleration", self.orb_acceleration).or_else(invalid)?;
+        if self.xp_base == 0 {
+            return invalid("xp_base must be at least 1".into());
+        }
+        if !(self.xp_growth >= 1.0 && self.xp_growth.is_finite()) {
+            return invalid(format!(
+                "xp_growth must be 1 or more (got {})",
+                self.xp_growth
+            ));
+        }
+        if self.upgrade_choices == 0 {
+            return invalid("upgrade_choices must be at least 1".into());
+        }
+        config::non_negative("squeeze_damage", self.squeeze_damage).or_else(invalid)?;
+        if !(0.0..=1.0).contains(&self.squeeze_slow) {
+            return invalid(format!(
+                "squeeze_slow must be between 0 and 1 (got {})",
+                self.squeeze_slow
+            ));
+        }
+        self.rope.validate()?;
+        self.archetypes.validate()?;
+        self.waves.validate()?;
+        self.waves.validate_against(&self.archetypes)?;
+        self.upgrades.validate()
+    }
+}
+
+pub struct World {
+    pub rope: Rope,
+    pub enemies: Vec<Enemy>,
+    pub health: Health,
+    /// Velocity of the head from steering and dashing, px/s.
+    pub head_velocity: Vec2,
+    /// Seconds left before the head can dash again.
+    pub dash_cooldown: f32,
+    /// Visible play area; enemies spawn just outside it.
+    pub arena: Rect,
+    pub tuning: Tuning,
+    /// Number of ticks stepped so far.
+    pub tick: u64,
+    /// Seconds of simulated time survived so far.
+    pub elapsed: f32,
+    pub director: Director,
+    /// Enemies spawned so far.
+    pub spawned: u32,
+    pub score: i32,
+    /// Dropped by kills and not yet picked up.
+    pub orbs: Vec<Orb>,
+    /// Experience toward the next level.
+    pub xp: u32,
+    pub level: u32,
+    /// Upgrades on offer, as indices into `tuning.upgrades`. The world stands
+    /// still while there are any, until [`Input::upgrade`] picks one.
+    pub choices: Vec<usize>,
+    /// Times each of `tuning.upgrades` has been taken.
+    pub taken: Vec<u32>,
+    pub boosts: Boosts,
+    /// Events since the frontend last took them.
+    pub events: Vec<Event>,
+    /// Seed the run was started with, for reproducing it later.
+    pub seed: u64,
+    pub rng: Rng,
+}
+
+impl World {
+    pub fn new(arena: Rect, seed: u64) -> Self {
+        Self::with_tuning(arena, seed, Tuning::default())
+    }
+
+    pub fn with_tuning(arena: Rect, seed: u64, tuning: Tuning) -> Self {
+        let start = Point2::new(0.0, 0.0);
+        let end = Point2::new(100.0, 0.0);
+
+        World {
+            rope: Rope::new(start, end, tuning.rope.clone()),
+            enemies: vec![],
+            health: Health::new(tuning.max_hp),
+            head_velocity: Vec2::ZERO,
+            dash_cooldown: 0.0,
+            director: Director::new(&tuning.waves),
+            spawned: 0,
+            taken: vec![0; tuning.upgrades.upgrades.len()],
+            arena,
+            tuning,
+            tick: 0,
+            elapsed: 0.0,
+            score: 0,
+            orbs: vec![],
+            xp: 0,
+            level: 1,
+            choices: vec![],
+            boosts: Boosts::default(),
+            events: vec![],
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// The run is over once the head runs out of hit points; `step` does
+    /// nothing from then on.
+    pub fn is_game_over(&self) -> bool {
+        self.health.is_dead()
+    }
+
+    /// Whether upgrades are on offer; `step` does nothing until one is
+    /// picked.
+    pub fn is_leveling_up(&self) -> bool {
+        !self.choices.is_empty()
+    }
+
+    /// XP the current level needs filled to level up.
+    pub fn xp_to_next_level(&self) -> u32 {
+        let growth = self.tuning.xp_growth.powi(self.level as i32 - 1);
+        (self.tuning.xp_base as f32 * growth).round() as u32
+    }
+
+    /// Advances the world by one tick of `dt` seconds, first taking the
+    /// upgrade `input` picks if any are on offer. While an offer is open and
+    /// nothing is picked, the world stands still and no tick is counted.
+    pub fn step(&mut self, input: &Input, dt: f32) {
+        if self.is_game_over() {
+            return;
+        }
+        if let Some(pick) = input.upgrade {
+            self.take_upgrade(pick as usize);
+        }
+        if self.is_leveling_up() {
+            return;
+        }
+
+        self.rope.begin_tick();
+        for enemy in self.enemies.iter_mut() {
+            enemy.begin_tick();
+        }
+        for orb in self.orbs.iter_mut() {
+            orb.begin_tick();
+        }
+
+        self.tick += 1;
+        self.elapsed += dt;
+        self.health.tick(dt);
+        self.health.heal(self.boosts.regen * dt);
+        self.dash_cooldown = (self.dash_cooldown - dt).max(0.0);
+        for enemy in self.enemies.iter_mut() {
+            enemy.hit_cooldown = (enemy.hit_cooldown - dt).max(0.0);
+        }
+        let substeps = 5; // Number of substeps for more accurate updates
+        let delta_time = dt / substeps as f32;
+        let drag_t = 1.0 - (-self.tuning.drag_rate * delta_time).exp();
+
+        for _ in 0..input.extend.unsigned_abs() {
+            if input.extend > 0 {
+                self.rope.extend();
+            } else {
+                self.rope.retract();
+            }
+        }
+
+        self.constrict(dt);
+        if input.dash {
+            self.dash(input);
+        }
+
+        let target_position = self.rope.points[0];
+        for _ in 0..substeps {
+            self.rope.update(delta_time);
+            for position in self.rope.tear_overstretched() {
+                self.events.push(Event::Tear { position });
+            }
+            self.steer_head(input.movement, delta_time);
+            self.flick_tip(input.flick, delta_time);
+            if let Some(point) = input
+                .drag_index
+                .and_then(|index| self.rope.points.get_mut(index))
+            {
+                *point = lerp(*point, input.cursor, drag_t);
+            }
+
+            // Update enemies to move towards the first rope point
+            for enemy in self.enemies.iter_mut() {
+                enemy.update(target_position, delta_time);
+            }
+
+            // Check for collisions
+            let hits = check_collisions(
+                &mut self.rope,
+                &mut self.enemies,
+                substeps,
+                self.tuning.continuous_collision,
+            );
+            self.apply_hits(&hits, delta_time);
+
+            if head_contact(&self.rope, &self.enemies) {
+                self.health
+                    .hit(self.tuning.contact_damage, self.tuning.invulnerability);
+            }
+            self.rope.pick_up();
+        }
+
+        self.remove_dead_enemies();
+        self.collect_orbs(dt);
+        self.level_up();
+        self.spawn_enemies(dt);
+        self.despawn_enemies();
+    }
+
+    /// Draws orbs near the head in and turns those that reach it into XP.
+    fn collect_orbs(&mut self, dt: f32) {
+        let head = self.rope.points[0];
+        let reach = self.rope.head_radius();
+        let (radius, acceleration) = (self.tuning.orb_magnet_radius, self.tuning.orb_acceleration);
+        let mut xp = 0;
+        self.orbs.retain_mut(|orb| {
+            let from = orb.position;
+            orb.update(head, radius, acceleration, dt);
+            // Swept, so a fast orb can't fly through the head
+            let t = closest_parameter(from, orb.position, head);
+            let touching = from.lerp(orb.position, t).distance(head) < reach + orb.radius();
+            if touching {
+                xp += orb.value;
+            }
+            !touching
+        });
+        self.xp += xp;
+    }
+
+    /// Offers upgrades once the XP bar is full. If every upgrade has been
+    /// taken as often as it can be, the level goes up with nothing on offer.
+    fn level_up(&mut self) {
+        let needed = self.xp_to_next_level();
+        if self.is_leveling_up() || self.xp < needed {
+            return;
+        }
+        self.xp -= needed;
+        self.level += 1;
+        self.choices = self.tuning.upgrades.offer(
+            &self.taken,
+            self.tuning.upgrade_choices as usize,
+            &mut self.rng,
+        );
+    }
+
+    /// Applies the offered upgrade at `pick`, if there is one, and closes the
+    /// offer.
+    fn take_upgrade(&mut self, pick: usize) {
+        let Some(&index) = self.choices.get(pick) else {
+            return;
+        };
+        self.choices.clear();
+        self.taken[index] += 1;
+        match self.tuning.upgrades.get(index).effect {
+            Effect::Segments { amount } => {
+                self.rope.params.max_segments += amount;
+                for _ in 0..amount {
+                    self.rope.extend();
+                }
+            }
+            Effect::TipMass { factor } => {
+                self.rope.set_tip_mass(self.rope.params.tip_mass * factor)
+            }
+            Effect::Damage { factor } => self.boosts.damage *= factor,
+            Effect::Speed { factor } => self.boosts.speed *= factor,
+            Effect::Regen { per_second } => self.boosts.regen += per_second,
+        }
+    }
+
+    /// Accelerates the head toward `movement` at full speed, or to a stop
+    /// when there is none, and moves it along.
+    fn steer_head(&mut self, movement: Vec2, delta_time: f32) {
+        let target = movement.clamp_length_max(1.0) * self.tuning.head_speed * self.boosts.speed;
+        let max_change = self.tuning.head_acceleration * delta_time;
+        self.head_velocity += (target - self.head_velocity).clamp_length_max(max_change);
+        self.rope.points[0] += self.head_velocity * delta_time;
+    }
+
+    /// Throws the head the way it's being steered, or else toward the cursor
+    /// it's dragged to, or else the way it's already going. The rope behind
+    /// is yanked after it and cracks like a whip.
+    fn dash(&mut self, input: &Input) {
+        if self.dash_cooldown > 0.0 {
+            return;
+        }
+        let head = self.rope.points[0];
+        let toward_cursor = match input.drag_index {
+            Some(0) => input.cursor - head,
+            _ => Vec2::ZERO,
+        };
+        let Some(direction) = input
+            .movement
+            .try_normalize()
+            .or_else(|| toward_cursor.try_normalize())
+            .or_else(|| self.head_velocity.try_normalize())
+        else {
+            return;
+        };
+        // The head is pinned rather than integrated, so its velocity lives
+        // here and not in `prev_points[0]`
+        self.head_velocity += direction * self.tuning.dash_speed;
+        self.dash_cooldown = self.tuning.dash_cooldown;
+        self.health.invulnerable_for = self
+            .health
+            .invulnerable_for
+            .max(self.tuning.dash_invulnerability);
+        self.events.push(Event::Dash { position: head });
+    }
+
+    /// Pushes the end of the attached rope toward `flick`.
+    fn flick_tip(&mut self, flick: Vec2, delta_time: f32) {
+        let tip = self.rope.attached_len() - 1;
+        if tip == 0 {
+            return;
+        }
+        let acceleration = flick.clamp_length_max(1.0) * self.tuning.flick_acceleration;
+        // Verlet velocity lives in the gap to the previous position
+        self.rope.prev_points[tip] -= acceleration * delta_time * delta_time;
+    }
+
+    /// Turns the fastest contact on each enemy into damage and knockback.
+    fn apply_hits(&mut self, hits: &[Hit], delta_time: f32) {
+        let mut strongest: Vec<Option<&Hit>> = vec![None; self.enemies.len()];
+        for hit in hits {
+            let best = &mut strongest[hit.enemy];
+            if best
+                .is_none_or(|best| hit.relative_velocity.length() > best.relative_velocity.length())
+            {
+                *best = Some(hit);
+            }
+        }
+
+        for (enemy, hit) in self.enemies.iter_mut().zip(strongest) {
+            let Some(hit) = hit else {
+                continue;
+            };
+            let speed = hit.relative_velocity.length() / delta_time;
+            if enemy.hit_cooldown > 0.0 || speed < self.tuning.min_hit_speed {
+                continue;
+            }
+            let amount = speed * self.tuning.whip_damage * self.boosts.damage;
+            enemy.hp -= amount;
+            enemy.hit_cooldown = self.tuning.hit_cooldown;
+            enemy.knock_back(hit.normal * hit.relative_velocity.length() * self.tuning.knockback);
+            self.events.push(Event::Damage {
+                position: hit.position,
+                amount,
+            });
+        }
+    }
+
+    /// Squeezes and slows every enemy the attached rope is looped around.
+    fn constrict(&mut self, dt: f32) {
+        let attached = &self.rope.points[..self.rope.attached_len()];
+        let (min, max) = attached
+            .iter()
+            .fold((attached[0], attached[0]), |(min, max), point| {
+                (min.min(*point), max.max(*point))
+            });
+        for enemy in self.enemies.iter_mut() {
+            let position = enemy.position;
+            // Only a rope that reaches all the way around can enclose it
+            let surrounded = position.cmpge(min).all() && position.cmple(max).all();
+            enemy.constricted =
+                surrounded && self.rope.winding(position).abs() >= self.tuning.constrict_turns;
+            if enemy.constricted {
+                enemy.hp -= self.tuning.squeeze_damage * self.boosts.damage * dt;
+                enemy.slow = 1.0 - self.tuning.squeeze_slow;
+            } else {
+                enemy.slow = 1.0;
+            }
+        }
+    }
+
+    /// Scores the dead and leaves an orb where each fell, worth its score in
+    /// XP and at least 1.
+    fn remove_dead_enemies(&mut self) {
+        let events = &mut self.events;
+        let orbs = &mut self.orbs;
+        let mut score = 0;
+        self.enemies.retain(|enemy| {
+            if enemy.is_dead() {
+                events.push(Event::Kill {
+                    position: enemy.position,
+                    radius: enemy.radius,
+                    color: enemy.color,
+                });
+                score += enemy.score;
+                orbs.push(Orb::new(enemy.position, enemy.score.max(1) as u32));
+            }
+            !enemy.is_dead()
+        });
+        self.score += score;
+    }
+
+    /// Hash of everything that influences future ticks, used by replays to
+    /// detect when playback has drifted from the recording.
+    pub fn checksum(&self) -> u64 {
+        let mut hash = Fnv::new();
+        hash.write_u64(self.tick);
+        hash.write_u64(self.rng.state());
+        for timer in self.director.timers() {
+            hash.write_f32(*timer);
+        }
+        hash.write_u64(self.score as u64);
+        hash.write_f32(self.health.hp);
+        hash.write_f32(self.health.invulnerable_for);
+        hash.write_point(self.head_velocity);
+        hash.write_f32(self.dash_cooldown);
+        hash.write_u64(self.xp as u64);
+        hash.write_u64(self.level as u64);
+        for choice in self.choices.iter() {
+            hash.write_u64(*choice as u64);
+        }
+        for taken in self.taken.iter() {
+            hash.write_u64(*taken as u64);
+        }
+        hash.write_f32(self.boosts.damage);
+        hash.write_f32(self.boosts.speed);
+        hash.write_f32(self.boosts.regen);
+        hash.write_f32(self.rope.params.tip_mass);
+        for orb in self.orbs.iter() {
+            hash.write_point(orb.position);
+            hash.write_point(orb.velocity);
+            hash.write_u64(orb.value as u64);
+        }
+        for (point, prev) in self.rope.points.iter().zip(&self.rope.prev_points) {
+            hash.write_point(*point);
+            hash.write_point(*prev);
+        }
+        for link in self.rope.links.iter() {
+            hash.write_f32(link.unwrap_or(-1.0));
+        }
+        for enemy in self.enemies.iter() {
+            hash.write_point(enemy.position);
+            hash.write_point(enemy.prev_position);
+            hash.write_f32(enemy.radius);
+            hash.write_f32(enemy.hp);
+            hash.write_f32(enemy.hit_cooldown);
+            hash.write_f32(enemy.behaviour_timer);
+            hash.write_point(enemy.dash_direction.unwrap_or_default());
+            hash.write_f32(enemy.slow);
+        }
+        hash.finish()
+    }
+
+    fn spawn_enemies(&mut self, dt: f32) {
+        let spawns = self.director.update(
+            &self.tuning.waves,
+            &self.tuning.archetypes,
+            self.arena,
+            self.elapsed,
+            dt,
+            &mut self.rng,
+        );
+        for spawn in spawns {
+            let rng = &mut self.rng;
+            let archetype = self.tuning.archetypes.get(spawn.archetype);
+            let [min_radius, max_radius] = archetype.radius;
+            let radius = rng.range(min_radius, max_radius);
+            let color = match archetype.color {
+                Some([r, g, b]) => Rgba::new(r, g, b, 1.0),
+                None => Rgba::new(rng.next_f32(), rng.next_f32(), rng.next_f32(), 1.0),
+            };
+            let phase = rng.next_f32();
+            self.enemies.push(Enemy::from_archetype(
+                archetype,
+                spawn.position,
+                radius,
+                color,
+                phase,
+            ));
+            self.spawned += 1;
+        }
+    }
+
+    fn despawn_enemies(&mut self) {
+        let win = self.arena;
+        let margin = 500.0; // Well beyond where the director spawns
+        let mut i = 0;
+        while i < self.enemies.len() {
+            let x = self.enemies[i].position.x;
+            let y = self.enemies[i].position.y;
+            let radius = self.enemies[i].radius;
+            if x + radius < win.left() - margin
+                || x - radius > win.right() + margin
+                || y + radius < win.bottom() - margin
+                || y - radius > win.top() + margin
+            {
+                self.enemies.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+/// FNV-1a, chosen over `DefaultHasher` because its output must stay stable
+/// across Rust releases for saved replays to keep verifying.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.write(&value.to_bits().to_le_bytes());
+    }
+
+    fn write_point(&mut self, point: Point2) {
+        self.write_f32(point.x);
+        self.write_f32(point.y);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub fn lerp(a: Point2, b: Point2, t: f32) -> Point2 {
+    let x = a.x + (b.x - a.x) * t;
+    let y = a.y + (b.y - a.y) * t;
+    Point2::new(x, y)
+}
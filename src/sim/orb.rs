@@ -0,0 +1,54 @@
+use nannou::prelude::*;
+
+/// Experience dropped by a killed enemy, drawn to the head once it's close.
+#[derive(Clone, Debug)]
+pub struct Orb {
+    pub position: Point2,
+    /// Position at the start of the current tick, for render interpolation.
+    pub tick_position: Point2,
+    /// Velocity in px/s.
+    pub velocity: Vec2,
+    /// Experience it's worth.
+    pub value: u32,
+}
+
+impl Orb {
+    pub fn new(position: Point2, value: u32) -> Self {
+        Orb {
+            position,
+            tick_position: position,
+            velocity: Vec2::ZERO,
+            value,
+        }
+    }
+
+    /// Radius it's drawn and picked up at, growing with its value.
+    pub fn radius(&self) -> f32 {
+        3.0 + (self.value as f32).sqrt()
+    }
+
+    pub fn begin_tick(&mut self) {
+        self.tick_position = self.position;
+    }
+
+    pub fn interpolated_position(&self, alpha: f32) -> Point2 {
+        self.tick_position.lerp(self.position, alpha)
+    }
+
+    /// Pulls the orb toward `head` at `acceleration` px/s² while within
+    /// `magnet_radius`, harder the closer it gets, and lets it drift to a
+    /// stop otherwise.
+    pub fn update(&mut self, head: Point2, magnet_radius: f32, acceleration: f32, dt: f32) {
+        let offset = head - self.position;
+        let distance = offset.length();
+        if distance < magnet_radius {
+            let pull = 1.0 + (1.0 - distance / magnet_radius) * 2.0;
+            self.velocity += offset.normalize_or_zero() * acceleration * pull * dt;
+            // Damped so it homes in rather than orbiting the head
+            self.velocity *= (-4.0 * dt).exp();
+        } else {
+            self.velocity *= (-8.0 * dt).exp();
+        }
+        self.position += self.velocity * dt;
+    }
+}
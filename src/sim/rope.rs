@@ -0,0 +1,450 @@
+use std::f32::consts::TAU;
+
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::collision::closest_parameter;
+use super::grid::SpatialHash;
+use crate::config::{self, ConfigError, Validate};
+
+/// The rope feel shipped with the game, also used by headless runs.
+const BUILTIN: &str = include_str!("../../assets/rope.toml");
+
+/// How segment lengths are held together each substep.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Solver {
+    /// Moves both ends of each segment toward its rest length by `stiffness`
+    /// of the error per iteration. How stretchy the rope feels depends on the
+    /// iteration and substep counts.
+    Relaxation { stiffness: f32 },
+    /// Extended position-based dynamics. `compliance` is the inverse stiffness
+    /// of each segment in s² per unit point mass, 0 being inextensible, and
+    /// gives the same stretch whatever the iteration and substep counts.
+    Xpbd { compliance: f32 },
+}
+
+/// Physical constants for the rope, as described in `assets/rope.toml`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RopeParams {
+    /// Acceleration on every point that isn't pinned, in px/s².
+    pub gravity: [f32; 2],
+    /// Fraction of velocity lost per second, as a rate in 1/s.
+    pub damping: f32,
+    /// Constraint solver passes per substep.
+    pub iterations: u32,
+    /// Segments at the start of a run.
+    pub segments: u32,
+    /// Most segments the rope can be extended to.
+    pub max_segments: u32,
+    /// Collision radius in px.
+    pub thickness: f32,
+    /// Mass of each point between the head and the tip, relative to an enemy
+    /// of mass 1.
+    pub point_mass: f32,
+    /// Mass of the last point; raise it for a flail.
+    pub tip_mass: f32,
+    /// How far past its rest length a segment stretches before it tears, as
+    /// a fraction of that length. Never tears when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tear_stretch: Option<f32>,
+    /// Keep points off segments other than their own, so the rope can bunch
+    /// and wrap instead of folding through itself.
+    pub self_collision: bool,
+    /// Speed in px/s at which a re-attached segment is pulled back to its
+    /// rest length.
+    pub reel_speed: f32,
+    // Last, as TOML needs plain values written before tables
+    pub solver: Solver,
+}
+
+impl Default for RopeParams {
+    fn default() -> Self {
+        config::parse(BUILTIN).expect("built-in rope.toml is valid")
+    }
+}
+
+impl Validate for RopeParams {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| Err(ConfigError::invalid(format!("rope: {message}")));
+        if self.gravity.iter().any(|g| !g.is_finite()) {
+            return invalid(format!("gravity must be finite (got {:?})", self.gravity));
+        }
+        if !(self.damping >= 0.0 && self.damping.is_finite()) {
+            return invalid(format!("damping must be 0 or more (got {})", self.damping));
+        }
+        match self.solver {
+            Solver::Relaxation { stiffness } => {
+                if !(stiffness > 0.0 && stiffness <= 1.0) {
+                    return invalid(format!(
+                        "stiffness must be above 0 and at most 1 (got {stiffness})"
+                    ));
+                }
+            }
+            Solver::Xpbd { compliance } => {
+                if !(compliance >= 0.0 && compliance.is_finite()) {
+                    return invalid(format!("compliance must be 0 or more (got {compliance})"));
+                }
+            }
+        }
+        if self.iterations == 0 {
+            return invalid("iterations must be at least 1".into());
+        }
+        if self.segments == 0 {
+            return invalid("segments must be at least 1".into());
+        }
+        if self.max_segments < self.segments {
+            return invalid(format!(
+                "max_segments must be at least segments (got {} < {})",
+                self.max_segments, self.segments
+            ));
+        }
+        config::positive("thickness", self.thickness).or_else(invalid)?;
+        config::positive("point_mass", self.point_mass).or_else(invalid)?;
+        config::positive("tip_mass", self.tip_mass).or_else(invalid)?;
+        if let Some(stretch) = self.tear_stretch {
+            config::positive("tear_stretch", stretch).or_else(invalid)?;
+        }
+        config::positive("reel_speed", self.reel_speed).or_else(invalid)?;
+        Ok(())
+    }
+}
+
+pub struct Rope {
+    pub points: Vec<Point2>,
+    pub prev_points: Vec<Point2>,
+    /// Positions at the start of the current tick, for render interpolation.
+    pub tick_points: Vec<Point2>,
+    pub segment_length: f32,
+    /// 1 / mass for each point. 0 pins a point in place (or makes it
+    /// infinitely heavy), as for the head, which follows the cursor.
+    pub inverse_masses: Vec<f32>,
+    /// Rest length of the segment from each point to the next, or `None`
+    /// where the rope has torn. Points past the first tear form loose pieces
+    /// that keep simulating until the head picks them back up.
+    pub links: Vec<Option<f32>>,
+    pub params: RopeParams,
+    pub color: Rgba,
+}
+
+impl Rope {
+    /// A straight rope from `start` to `end`, divided into `params.segments`.
+    pub fn new(start: Point2, end: Point2, params: RopeParams) -> Self {
+        let count = params.segments as usize + 1;
+        let length = start.distance(end);
+        let segment_length = length / (count as f32 - 1.0);
+        let direction = (end - start).normalize();
+
+        let points: Vec<Point2> = (0..count)
+            .map(|i| start + direction * segment_length * i as f32)
+            .collect();
+
+        let prev_points = points.clone();
+        let tick_points = points.clone();
+        let inverse_masses = (0..count)
+            .map(|i| match i {
+                0 => 0.0,
+                _ if i == count - 1 => 1.0 / params.tip_mass,
+                _ => 1.0 / params.point_mass,
+            })
+            .collect();
+
+        Rope {
+            links: vec![Some(segment_length); count - 1],
+            points,
+            prev_points,
+            tick_points,
+            segment_length,
+            inverse_masses,
+            params,
+            color: Rgba::new(1.0, 1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Collision radius in px.
+    pub fn thickness(&self) -> f32 {
+        self.params.thickness
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.update_rope(delta_time);
+    }
+
+    fn update_rope(&mut self, delta_time: f32) {
+        let gravity = Vec2::from(self.params.gravity) * delta_time * delta_time;
+        let retained = (-self.params.damping * delta_time).exp();
+
+        for i in 0..self.points.len() {
+            if self.inverse_masses[i] == 0.0 {
+                // Pinned points aren't integrated, but tracking where they were
+                // lets hits read their velocity
+                self.prev_points[i] = self.points[i];
+                continue;
+            }
+            let current = self.points[i];
+            let prev = self.prev_points[i];
+            let velocity = current - prev;
+            let next_position = current + velocity * retained + gravity;
+            self.prev_points[i] = self.points[i];
+            self.points[i] = next_position;
+        }
+
+        // Re-attached segments start at whatever length they were picked up at
+        let reel = self.params.reel_speed * delta_time;
+        for rest in self.links.iter_mut().flatten() {
+            *rest += (self.segment_length - *rest).clamp(-reel, reel);
+        }
+
+        match self.params.solver {
+            Solver::Relaxation { stiffness } => {
+                for _ in 0..self.params.iterations {
+                    self.constrain_points(stiffness);
+                }
+            }
+            Solver::Xpbd { compliance } => self.solve_xpbd(compliance, delta_time),
+        }
+
+        if self.params.self_collision {
+            self.collide_self();
+        }
+    }
+
+    /// Pushes points out of segments they aren't part of, found through a
+    /// [`SpatialHash`] of the points so long ropes stay cheap.
+    fn collide_self(&mut self) {
+        let gap = self.params.thickness * 2.0;
+        let padding = vec2(1.0, 1.0) * gap;
+        let mut grid = SpatialHash::new(self.segment_length.max(gap));
+        for (index, point) in self.points.iter().enumerate() {
+            grid.insert(index, *point);
+        }
+
+        let mut nearby = vec![];
+        for i in 0..self.links.len() {
+            if self.links[i].is_none() {
+                continue;
+            }
+            let (a, b) = (self.points[i], self.points[i + 1]);
+            nearby.clear();
+            grid.query(a.min(b) - padding, a.max(b) + padding, &mut nearby);
+            for &k in nearby.iter() {
+                // Points this close along the rope touch it anyway at a sharp bend
+                if k + 2 >= i && k <= i + 3 {
+                    continue;
+                }
+                self.push_off_segment(k, i, gap);
+            }
+        }
+    }
+
+    /// Separates point `k` from segment `i` to at least `gap`, sharing the
+    /// correction by inverse mass as enemy contacts do.
+    fn push_off_segment(&mut self, k: usize, i: usize, gap: f32) {
+        let (a, b, point) = (self.points[i], self.points[i + 1], self.points[k]);
+        let t = closest_parameter(a, b, point);
+        let offset = point - a.lerp(b, t);
+        let distance = offset.length();
+        if distance >= gap {
+            return;
+        }
+        let normal = if distance > f32::EPSILON {
+            offset / distance
+        } else {
+            (b - a).perp().try_normalize().unwrap_or(Vec2::Y)
+        };
+
+        let weight_a = (1.0 - t) * self.inverse_masses[i];
+        let weight_b = t * self.inverse_masses[i + 1];
+        let weight_point = self.inverse_masses[k];
+        let total = weight_point + (1.0 - t) * weight_a + t * weight_b;
+        if total == 0.0 {
+            return;
+        }
+        let correction = normal * (gap - distance) / total;
+        self.points[k] += correction * weight_point;
+        self.points[i] -= correction * weight_a;
+        self.points[i + 1] -= correction * weight_b;
+    }
+
+    fn constrain_points(&mut self, stiffness: f32) {
+        for i in 0..(self.points.len() - 1) {
+            let Some(rest) = self.links[i] else {
+                continue;
+            };
+            let weight_a = self.inverse_masses[i];
+            let weight_b = self.inverse_masses[i + 1];
+            let total = weight_a + weight_b;
+            if total == 0.0 {
+                continue;
+            }
+            let point_a = self.points[i];
+            let point_b = self.points[i + 1];
+            let delta = point_b - point_a;
+            let distance = delta.length();
+            let difference = rest - distance;
+            // Lighter ends take more of the correction
+            let correction = delta.normalize() * (difference * stiffness / total);
+            self.points[i] -= correction * weight_a;
+            self.points[i + 1] += correction * weight_b;
+        }
+    }
+
+    fn solve_xpbd(&mut self, compliance: f32, delta_time: f32) {
+        let alpha = compliance / (delta_time * delta_time);
+        // Accumulated multiplier per segment, which is what makes the result
+        // independent of the iteration count
+        let mut lambdas = vec![0.0; self.points.len() - 1];
+        for _ in 0..self.params.iterations {
+            for (i, lambda) in lambdas.iter_mut().enumerate() {
+                let Some(rest) = self.links[i] else {
+                    continue;
+                };
+                let weight_a = self.inverse_masses[i];
+                let weight_b = self.inverse_masses[i + 1];
+                if weight_a + weight_b == 0.0 {
+                    continue;
+                }
+
+                let delta = self.points[i + 1] - self.points[i];
+                let distance = delta.length();
+                if distance <= f32::EPSILON {
+                    continue;
+                }
+                let normal = delta / distance;
+                let error = distance - rest;
+                let step = (-error - alpha * *lambda) / (weight_a + weight_b + alpha);
+                *lambda += step;
+                self.points[i] -= normal * step * weight_a;
+                self.points[i + 1] += normal * step * weight_b;
+            }
+        }
+    }
+
+    /// Adds a segment next to the head, up to `max_segments`. The new point
+    /// starts halfway along the first segment, moving with it, and both halves
+    /// grow to full length at `reel_speed` so the rest of the chain carries on
+    /// undisturbed.
+    pub fn extend(&mut self) -> bool {
+        if self.links.len() >= self.params.max_segments as usize {
+            return false;
+        }
+        let Some(rest) = self.links[0] else {
+            return false;
+        };
+        let midpoint = |points: &[Point2]| points[0].lerp(points[1], 0.5);
+        self.points.insert(1, midpoint(&self.points));
+        self.prev_points.insert(1, midpoint(&self.prev_points));
+        self.tick_points.insert(1, midpoint(&self.tick_points));
+        self.inverse_masses.insert(1, 1.0 / self.params.point_mass);
+        self.links[0] = Some(rest / 2.0);
+        self.links.insert(0, Some(rest / 2.0));
+        true
+    }
+
+    /// Removes the point next to the head, keeping at least one segment. The
+    /// head's new neighbour keeps its position and velocity; the joined
+    /// segment starts at its current length and reels in.
+    pub fn retract(&mut self) -> bool {
+        if self.links.len() < 2 || self.links[0].is_none() || self.links[1].is_none() {
+            return false;
+        }
+        self.points.remove(1);
+        self.prev_points.remove(1);
+        self.tick_points.remove(1);
+        self.inverse_masses.remove(1);
+        self.links.remove(0);
+        self.links[0] = Some(self.points[0].distance(self.points[1]));
+        true
+    }
+
+    /// Changes the mass of the last point, as for a heavier flail.
+    pub fn set_tip_mass(&mut self, mass: f32) {
+        self.params.tip_mass = mass;
+        let tip = self.inverse_masses.len() - 1;
+        self.inverse_masses[tip] = 1.0 / mass;
+    }
+
+    /// Number of points still connected to the head.
+    pub fn attached_len(&self) -> usize {
+        self.links
+            .iter()
+            .position(Option::is_none)
+            .map_or(self.points.len(), |link| link + 1)
+    }
+
+    /// Turns the attached rope makes around `point`, counter-clockwise
+    /// positive. About 1 when the rope is looped once around it, whatever
+    /// the loop's shape; well under 1 for a rope that only bends past it.
+    pub fn winding(&self, point: Point2) -> f32 {
+        let attached = &self.points[..self.attached_len()];
+        let angle: f32 = attached
+            .windows(2)
+            .map(|pair| {
+                let (a, b) = (pair[0] - point, pair[1] - point);
+                a.perp_dot(b).atan2(a.dot(b))
+            })
+            .sum();
+        angle / TAU
+    }
+
+    /// Breaks the segment from point `link` to the next.
+    pub fn tear(&mut self, link: usize) {
+        self.links[link] = None;
+    }
+
+    /// Tears every segment stretched past `tear_stretch`, returning where.
+    pub fn tear_overstretched(&mut self) -> Vec<Point2> {
+        let Some(stretch) = self.params.tear_stretch else {
+            return vec![];
+        };
+        let mut tears = vec![];
+        for (i, link) in self.links.iter_mut().enumerate() {
+            let (a, b) = (self.points[i], self.points[i + 1]);
+            if link.is_some_and(|rest| a.distance(b) > rest * (1.0 + stretch)) {
+                *link = None;
+                tears.push(a.lerp(b, 0.5));
+            }
+        }
+        tears
+    }
+
+    /// Reconnects loose pieces if the head is touching one, up to and
+    /// including the piece touched. The rejoined segments keep their current
+    /// length and reel back in over the following substeps.
+    pub fn pick_up(&mut self) -> bool {
+        let head = self.points[0];
+        let reach = self.head_radius() + self.params.thickness;
+        let attached = self.attached_len();
+        let Some(touched) =
+            (attached..self.points.len()).find(|&i| self.points[i].distance(head) < reach)
+        else {
+            return false;
+        };
+        for i in attached - 1..touched {
+            if self.links[i].is_none() {
+                self.links[i] = Some(self.points[i].distance(self.points[i + 1]));
+            }
+        }
+        true
+    }
+
+    /// Radius of the head point, which is drawn larger and takes contact damage.
+    pub fn head_radius(&self) -> f32 {
+        self.params.thickness * 2.0
+    }
+
+    /// Remembers the current positions as the start of a new tick.
+    pub fn begin_tick(&mut self) {
+        self.tick_points.clone_from(&self.points);
+    }
+
+    /// Point positions blended between the last two ticks.
+    pub fn interpolated_points(&self, alpha: f32) -> Vec<Point2> {
+        self.tick_points
+            .iter()
+            .zip(self.points.iter())
+            .map(|(prev, current)| prev.lerp(*current, alpha))
+            .collect()
+    }
+}
@@ -0,0 +1,39 @@
+/// Upper bound on ticks run for a single frame, so a long stall (window drag,
+/// breakpoint) doesn't make the sim spiral trying to catch up.
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// Accumulates real elapsed time and hands it out in fixed-size ticks.
+pub struct FixedTimestep {
+    pub tick: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(tick: f32) -> Self {
+        FixedTimestep {
+            tick,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Adds `elapsed` seconds and returns how many ticks should be stepped.
+    pub fn advance(&mut self, elapsed: f32) -> u32 {
+        self.accumulator += elapsed;
+        let mut ticks = 0;
+        while self.accumulator >= self.tick {
+            if ticks == MAX_TICKS_PER_FRAME {
+                // Drop the backlog instead of falling further behind
+                self.accumulator %= self.tick;
+                break;
+            }
+            self.accumulator -= self.tick;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /// How far the current frame is between the last tick and the next, in `0..1`.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.tick
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+use super::rng::Rng;
+use crate::config::{self, ConfigError, Validate};
+
+/// The upgrades shipped with the game, also used by headless runs.
+const BUILTIN: &str = include_str!("../../assets/upgrades.toml");
+
+/// What taking an upgrade does. Every effect stacks with itself.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Effect {
+    /// Adds `amount` segments at the head and raises the most the rope can
+    /// be extended to by as many.
+    Segments { amount: u32 },
+    /// Multiplies the mass of the rope's last point.
+    TipMass { factor: f32 },
+    /// Multiplies the damage the rope deals, by whipping and by squeezing.
+    Damage { factor: f32 },
+    /// Multiplies the top speed of the steered head.
+    Speed { factor: f32 },
+    /// Heals the head by `per_second` hit points every second.
+    Regen { per_second: f32 },
+}
+
+/// What the upgrades taken so far add up to, for the effects that aren't
+/// kept on the rope itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Boosts {
+    /// Multiplier on rope damage.
+    pub damage: f32,
+    /// Multiplier on the head's top speed.
+    pub speed: f32,
+    /// Hit points healed per second.
+    pub regen: f32,
+}
+
+impl Default for Boosts {
+    fn default() -> Self {
+        Boosts {
+            damage: 1.0,
+            speed: 1.0,
+            regen: 0.0,
+        }
+    }
+}
+
+/// A choice offered on levelling up, as described in `assets/upgrades.toml`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Upgrade {
+    pub name: String,
+    /// Shown on the level-up screen.
+    pub label: String,
+    pub description: String,
+    /// Times it can be taken in one run; unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+    pub effect: Effect,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Upgrades {
+    #[serde(rename = "upgrade", default)]
+    pub upgrades: Vec<Upgrade>,
+}
+
+impl Default for Upgrades {
+    fn default() -> Self {
+        config::parse(BUILTIN).expect("built-in upgrades.toml is valid")
+    }
+}
+
+impl Upgrades {
+    pub fn get(&self, index: usize) -> &Upgrade {
+        &self.upgrades[index]
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.upgrades
+            .iter()
+            .position(|upgrade| upgrade.name == name)
+    }
+
+    /// Picks up to `count` different upgrades that haven't reached their
+    /// `max`, given how many times each was `taken`.
+    pub fn offer(&self, taken: &[u32], count: usize, rng: &mut Rng) -> Vec<usize> {
+        let mut available: Vec<usize> = (0..self.upgrades.len())
+            .filter(|&index| {
+                self.upgrades[index]
+                    .max
+                    .is_none_or(|max| taken[index] < max)
+            })
+            .collect();
+        let mut offer = vec![];
+        while offer.len() < count && !available.is_empty() {
+            let pick = (rng.next_f32() * available.len() as f32) as usize;
+            offer.push(available.remove(pick.min(available.len() - 1)));
+        }
+        offer
+    }
+}
+
+impl Validate for Upgrades {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (index, upgrade) in self.upgrades.iter().enumerate() {
+            if self.upgrades[..index]
+                .iter()
+                .any(|other| other.name == upgrade.name)
+            {
+                return Err(ConfigError::invalid(format!(
+                    "upgrade `{}` is defined twice",
+                    upgrade.name
+                )));
+            }
+            upgrade.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl Validate for Upgrade {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| {
+            let name = config::display_name(&self.name);
+            Err(ConfigError::invalid(format!("upgrade `{name}`: {message}")))
+        };
+
+        if self.name.trim().is_empty() {
+            return invalid("name must not be empty".into());
+        }
+        if self.max == Some(0) {
+            return invalid("max must be at least 1".into());
+        }
+        match self.effect {
+            Effect::Segments { amount } => {
+                if amount == 0 {
+                    return invalid("amount must be at least 1".into());
+                }
+            }
+            Effect::TipMass { factor } | Effect::Damage { factor } | Effect::Speed { factor } => {
+                config::positive("factor", factor).or_else(invalid)?;
+            }
+            Effect::Regen { per_second } => {
+                config::positive("per_second", per_second).or_else(invalid)?;
+            }
+        }
+        Ok(())
+    }
+}
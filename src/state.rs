@@ -0,0 +1,64 @@
+//! Which screen the frontend is showing, and so whether the run advances.
+//!
+//! The [`World`] itself only knows whether the head is dead and whether
+//! upgrades are on offer; everything else here is the player's doing, and
+//! none of it is recorded in replays.
+
+use crate::sim::World;
+
+/// The screens of a live run. Only [`State::Playing`] steps the world.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum State {
+    /// Before the first run; any button starts it.
+    #[default]
+    Title,
+    Playing,
+    /// Held still by the pause action or by the window losing focus.
+    Paused,
+    /// Held still while the player picks an upgrade.
+    LevelUp,
+    /// The head ran out of hit points; the restart action starts a new run.
+    GameOver,
+}
+
+impl State {
+    /// Whether the world steps in this state.
+    pub fn is_running(self) -> bool {
+        self == State::Playing
+    }
+
+    /// Whether there is a run on screen, as opposed to the title.
+    pub fn shows_run(self) -> bool {
+        self != State::Title
+    }
+
+    /// Where the pause action leads; only a run in progress can be paused or
+    /// resumed.
+    pub fn toggle_pause(self) -> State {
+        match self {
+            State::Playing => State::Paused,
+            State::Paused => State::Playing,
+            state => state,
+        }
+    }
+
+    /// Where losing window focus leads. Regaining it doesn't resume, so the
+    /// player isn't thrown back in before looking.
+    pub fn unfocus(self) -> State {
+        match self {
+            State::Playing => State::Paused,
+            state => state,
+        }
+    }
+
+    /// Where a step that left the world as `world` leads.
+    pub fn after_step(self, world: &World) -> State {
+        if world.is_game_over() {
+            State::GameOver
+        } else if world.is_leveling_up() {
+            State::LevelUp
+        } else {
+            self
+        }
+    }
+}
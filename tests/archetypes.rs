@@ -0,0 +1,144 @@
+mod common;
+
+use nannou::prelude::*;
+use survivor::config::{self, ConfigError};
+use survivor::sim::{Archetypes, Behaviour, Enemy, Rng};
+
+const VALID: &str = r#"
+[[enemy]]
+name = "grunt"
+speed = 100.0
+acceleration = 150.0
+mass = 1.0
+radius = [10.0, 12.0]
+hp = 20.0
+score = 1
+spawn_weight = 3.0
+behaviour = { kind = "homing" }
+
+[[enemy]]
+name = "tank"
+speed = 50.0
+acceleration = 50.0
+mass = 4.0
+radius = [30.0, 30.0]
+hp = 100.0
+color = [0.5, 0.5, 0.5]
+score = 5
+spawn_weight = 1.0
+behaviour = { kind = "homing" }
+"#;
+
+fn parse_error(text: &str) -> String {
+    common::parse_error::<Archetypes>(text)
+}
+
+#[test]
+fn builtin_archetypes_are_valid() {
+    let archetypes = Archetypes::default();
+    for name in ["grunt", "swarmer", "tank", "charger"] {
+        assert!(archetypes.find(name).is_some(), "missing {name}");
+    }
+}
+
+#[test]
+fn shipped_file_matches_builtin() {
+    common::assert_shipped_matches_builtin::<Archetypes>("enemies.toml");
+}
+
+#[test]
+fn choose_follows_spawn_weights() {
+    let archetypes: Archetypes = config::parse(VALID).unwrap();
+    let tank = archetypes.find("tank").unwrap();
+    let mut rng = Rng::new(9);
+    let tanks = (0..4000)
+        .filter(|_| archetypes.choose(&mut rng) == tank)
+        .count();
+    assert!((800..1200).contains(&tanks), "{tanks} tanks out of 4000");
+}
+
+#[test]
+fn rejects_unknown_fields() {
+    let text = VALID.replace("hp = 20.0", "hp = 20.0\nhealth = 3.0");
+    assert!(parse_error(&text).contains("unknown field `health`"));
+}
+
+#[test]
+fn rejects_unknown_behaviour() {
+    let text = VALID.replace(r#"kind = "homing" }"#, r#"kind = "teleport" }"#);
+    assert!(parse_error(&text).contains("teleport"));
+}
+
+#[test]
+fn names_the_archetype_and_field_when_invalid() {
+    let text = VALID.replace("mass = 4.0", "mass = 0.0");
+    assert_eq!(
+        parse_error(&text),
+        "archetype `tank`: mass must be a positive number (got 0)"
+    );
+
+    let text = VALID.replace("radius = [10.0, 12.0]", "radius = [12.0, 10.0]");
+    assert!(parse_error(&text).contains("archetype `grunt`: radius range"));
+
+    let text = VALID.replace(r#"name = "tank""#, r#"name = "grunt""#);
+    assert_eq!(parse_error(&text), "archetype `grunt` is defined twice");
+}
+
+#[test]
+fn needs_something_to_spawn() {
+    let text = VALID.replace("spawn_weight = 3.0", "spawn_weight = 0.0");
+    let text = text.replace("spawn_weight = 1.0", "spawn_weight = 0.0");
+    assert!(parse_error(&text).contains("spawn_weight above 0"));
+}
+
+#[test]
+fn load_errors_name_the_file() {
+    let err = config::load::<Archetypes>("does/not/exist.toml").unwrap_err();
+    assert!(matches!(err, ConfigError::File { .. }));
+    assert!(err.to_string().starts_with("does/not/exist.toml: "));
+}
+
+fn simulate(archetype: &str, seconds: f32) -> Enemy {
+    let archetypes = Archetypes::default();
+    let archetype = archetypes.get(archetypes.find(archetype).unwrap());
+    let mut enemy = Enemy::from_archetype(
+        archetype,
+        pt2(-400.0, 0.0),
+        10.0,
+        Rgba::new(1.0, 1.0, 1.0, 1.0),
+        0.25,
+    );
+    let dt = 1.0 / 300.0;
+    for _ in 0..(seconds / dt) as usize {
+        enemy.update(pt2(0.0, 0.0), dt);
+    }
+    enemy
+}
+
+#[test]
+fn homing_enemies_respect_top_speed() {
+    let grunt = simulate("grunt", 5.0);
+    assert!(grunt.velocity().length() <= grunt.speed / 300.0 + 1e-4);
+}
+
+#[test]
+fn swarmers_weave_off_the_direct_line() {
+    let swarmer = simulate("swarmer", 0.5);
+    assert!(swarmer.position.y.abs() > 1.0);
+}
+
+#[test]
+fn chargers_dash_faster_than_they_walk() {
+    let archetypes = Archetypes::default();
+    let charger = archetypes.get(archetypes.find("charger").unwrap());
+    let Behaviour::Charger {
+        windup, dash_speed, ..
+    } = charger.behaviour
+    else {
+        panic!("charger should charge");
+    };
+    let enemy = simulate("charger", windup + 0.1);
+    let speed = enemy.velocity().length() * 300.0;
+    assert!(speed > charger.speed);
+    assert!(speed <= dash_speed + 1.0);
+}
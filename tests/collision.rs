@@ -0,0 +1,245 @@
+use nannou::prelude::*;
+use survivor::sim::{
+    check_collisions, check_collisions_brute_force, Enemy, Rng, Rope, RopeParams, SpatialHash,
+};
+
+const SUBSTEPS: i32 = 5;
+
+fn segments(segments: u32) -> RopeParams {
+    RopeParams {
+        segments,
+        ..RopeParams::default()
+    }
+}
+
+/// A horizontal rope with points 40px apart, wider than any enemy.
+fn rope() -> Rope {
+    Rope::new(pt2(-100.0, 0.0), pt2(100.0, 0.0), segments(5))
+}
+
+fn enemy(position: Point2, radius: f32) -> Enemy {
+    Enemy::new(position, radius, Rgba::new(1.0, 1.0, 1.0, 1.0), 10.0)
+}
+
+#[test]
+fn enemies_between_points_hit_the_segment() {
+    let mut rope = rope();
+    let mut enemies = [enemy(pt2(-40.0, 6.0), 5.0)];
+    let hits = check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].position, pt2(-40.0, 0.0));
+    assert_eq!(hits[0].normal, vec2(0.0, 1.0));
+    // Pushed clear of the capsule, the segment moving the other way
+    let clearance = enemies[0].position.y - rope.points[1].lerp(rope.points[2], 0.5).y;
+    assert!((clearance - (5.0 + rope.thickness())).abs() < 1e-4);
+    assert!(rope.points[1].y < 0.0 && rope.points[2].y < 0.0);
+}
+
+#[test]
+fn response_is_split_between_endpoints_by_distance() {
+    let mut rope = rope();
+    // A quarter of the way from point 1 (x = -60) to point 2 (x = -20)
+    let mut enemies = [enemy(pt2(-50.0, 6.0), 5.0)];
+    check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+
+    let near = -rope.points[1].y;
+    let far = -rope.points[2].y;
+    assert!((near / far - 3.0).abs() < 1e-3, "{near} vs {far}");
+    assert_eq!(rope.points[0], pt2(-100.0, 0.0));
+    assert_eq!(rope.points[3], pt2(20.0, 0.0));
+}
+
+#[test]
+fn nothing_collides_to_the_side_of_a_point() {
+    let mut rope = Rope::new(pt2(0.0, -100.0), pt2(0.0, 100.0), segments(5));
+    // Right of a point by more than the radius plus rope thickness
+    let mut enemies = [enemy(pt2(10.0, -20.0), 5.0)];
+    let hits = check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+
+    assert!(hits.is_empty());
+    assert_eq!(enemies[0].position, pt2(10.0, -20.0));
+}
+
+#[test]
+fn fast_enemies_cannot_pass_between_points() {
+    let mut rope = rope();
+    let target = pt2(-40.0, -500.0);
+    let mut fast = enemy(pt2(-40.0, 60.0), 5.0);
+    fast.speed = 1200.0;
+    fast.acceleration = 100_000.0;
+    let mut enemies = [fast];
+
+    let delta_time = 1.0 / 60.0 / SUBSTEPS as f32;
+    let mut touched = false;
+    for _ in 0..120 * SUBSTEPS {
+        enemies[0].update(target, delta_time);
+        touched |= !check_collisions(&mut rope, &mut enemies, SUBSTEPS, true).is_empty();
+
+        // Still on the near side of the segment it's pressing against
+        let (a, b) = (rope.points[1], rope.points[2]);
+        let side = (b - a).perp_dot(enemies[0].position - a);
+        assert!(
+            side > 0.0,
+            "enemy crossed the rope at {}",
+            enemies[0].position
+        );
+    }
+    assert!(touched);
+}
+
+/// A two-point rope whose tail has just swung from below an enemy at the
+/// origin to above it, further than the enemy's diameter in one substep.
+fn whipped_rope() -> (Rope, [Enemy; 1]) {
+    let mut rope = Rope::new(pt2(-200.0, 0.0), pt2(0.0, 50.0), segments(1));
+    rope.prev_points[1] = pt2(0.0, -50.0);
+    (rope, [enemy(pt2(0.0, 0.0), 10.0)])
+}
+
+#[test]
+fn discrete_checks_miss_a_tunnelling_tip() {
+    let (mut rope, mut enemies) = whipped_rope();
+    assert!(check_collisions(&mut rope, &mut enemies, SUBSTEPS, false).is_empty());
+}
+
+#[test]
+fn swept_checks_catch_a_tunnelling_tip() {
+    let (mut rope, mut enemies) = whipped_rope();
+    let hits = check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+
+    assert_eq!(hits.len(), 1);
+    let hit = hits[0];
+    assert_eq!(hit.enemy, 0);
+    let reach = 10.0 + rope.thickness();
+    assert!(
+        hit.position.distance(pt2(0.0, -reach)) < 1e-3,
+        "{}",
+        hit.position
+    );
+    assert!(hit.normal.distance(vec2(0.0, 1.0)) < 1e-3);
+    assert!(hit.relative_velocity.distance(vec2(0.0, 100.0)) < 1e-3);
+    // Registered, not resolved: the tip keeps its momentum
+    assert!(rope.points[1].distance(pt2(0.0, 50.0)) < 1e-3);
+}
+
+#[test]
+fn swept_checks_ignore_points_already_touching() {
+    let mut rope = rope();
+    let mut enemies = [enemy(pt2(-40.0, 6.0), 5.0)];
+    let hits = check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+    assert_eq!(hits.len(), 1);
+}
+
+#[test]
+fn spatial_hash_finds_nearby_items_in_order() {
+    let mut grid = SpatialHash::new(20.0);
+    grid.insert(3, pt2(5.0, 5.0));
+    grid.insert(1, pt2(-15.0, 12.0));
+    grid.insert(2, pt2(300.0, 0.0));
+    grid.insert(0, pt2(25.0, -5.0));
+
+    let mut found = vec![];
+    grid.query(pt2(-10.0, -10.0), pt2(30.0, 10.0), &mut found);
+    assert_eq!(found, [0, 1, 3]);
+}
+
+/// Enemies crowded around a rope that's mid-swing, so they touch the rope and
+/// each other.
+fn crowd(seed: u64) -> (Rope, Vec<Enemy>) {
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), RopeParams::default());
+    for (i, prev) in rope.prev_points.iter_mut().enumerate() {
+        *prev -= vec2(0.0, i as f32 * 3.0);
+    }
+    let mut rng = Rng::new(seed);
+    let enemies = (0..80)
+        .map(|_| {
+            let position = pt2(rng.range(-60.0, 160.0), rng.range(-80.0, 80.0));
+            enemy(position, rng.range(8.0, 20.0))
+        })
+        .collect();
+    (rope, enemies)
+}
+
+#[test]
+fn broadphase_matches_testing_every_pair() {
+    for seed in 0..5 {
+        let (mut rope, mut enemies) = crowd(seed);
+        let (mut reference_rope, mut reference_enemies) = crowd(seed);
+
+        let delta_time = 1.0 / 60.0 / SUBSTEPS as f32;
+        for substep in 0..120 {
+            let head = rope.points[0];
+            for (enemy, reference) in enemies.iter_mut().zip(reference_enemies.iter_mut()) {
+                enemy.update(head, delta_time);
+                reference.update(head, delta_time);
+            }
+            rope.update(delta_time);
+            reference_rope.update(delta_time);
+
+            let hits = check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+            let expected = check_collisions_brute_force(
+                &mut reference_rope,
+                &mut reference_enemies,
+                SUBSTEPS,
+                true,
+            );
+            assert_eq!(hits, expected, "seed {seed}, substep {substep}");
+            assert_eq!(rope.points, reference_rope.points);
+            for (enemy, reference) in enemies.iter().zip(reference_enemies.iter()) {
+                assert_eq!(enemy.position, reference.position);
+            }
+        }
+    }
+}
+
+/// How far an enemy of `enemy_mass` touching the tip of a rope with a
+/// `tip_mass` tip, and the tip itself, get pushed.
+fn push_at_tip(enemy_mass: f32, tip_mass: f32) -> (f32, f32) {
+    let params = RopeParams {
+        tip_mass,
+        ..segments(5)
+    };
+    let mut rope = Rope::new(pt2(-100.0, 0.0), pt2(100.0, 0.0), params);
+    let mut touching = enemy(pt2(100.0, 6.0), 5.0);
+    touching.mass = enemy_mass;
+    let mut enemies = [touching];
+    check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+    (enemies[0].position.y - 6.0, -rope.points[5].y)
+}
+
+#[test]
+fn rope_and_enemy_share_the_push_by_mass() {
+    let (enemy, tip) = push_at_tip(1.0, 1.0);
+    assert!((enemy - tip).abs() < 1e-4, "{enemy} vs {tip}");
+
+    let (enemy, tip) = push_at_tip(1.0, 4.0);
+    assert!((enemy / tip - 4.0).abs() < 1e-3, "{enemy} vs {tip}");
+
+    let (enemy, tip) = push_at_tip(4.0, 1.0);
+    assert!((tip / enemy - 4.0).abs() < 1e-3, "{enemy} vs {tip}");
+}
+
+#[test]
+fn pinned_points_are_not_pushed() {
+    let mut rope = rope();
+    rope.inverse_masses[5] = 0.0;
+    let mut enemies = [enemy(pt2(100.0, 6.0), 5.0)];
+    check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+    assert_eq!(rope.points[5], pt2(100.0, 0.0));
+    assert!((enemies[0].position.y - (5.0 + rope.thickness())).abs() < 1e-4);
+}
+
+#[test]
+fn heavier_enemies_shove_lighter_ones_aside() {
+    let mut rope = rope();
+    let mut light = enemy(pt2(0.0, 100.0), 10.0);
+    let mut heavy = enemy(pt2(15.0, 100.0), 10.0);
+    light.mass = 1.0;
+    heavy.mass = 3.0;
+    let mut enemies = [light, heavy];
+    check_collisions(&mut rope, &mut enemies, SUBSTEPS, true);
+
+    let light_moved = -enemies[0].position.x;
+    let heavy_moved = enemies[1].position.x - 15.0;
+    assert!((light_moved / heavy_moved - 3.0).abs() < 1e-3);
+}
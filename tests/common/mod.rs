@@ -0,0 +1,24 @@
+//! Helpers shared by the config file tests.
+
+// Each test binary uses only some of them
+#![allow(dead_code)]
+
+use std::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use survivor::config::{self, Validate};
+
+/// The error parsing `text` gives, as shown to the player.
+pub fn parse_error<T: DeserializeOwned + Validate + Debug>(text: &str) -> String {
+    config::parse::<T>(text).unwrap_err().to_string()
+}
+
+/// Checks that `assets/<file>` loads to the same value as `T::default()`.
+pub fn assert_shipped_matches_builtin<T>(file: &str)
+where
+    T: DeserializeOwned + Validate + Default + PartialEq + Debug,
+{
+    let path = format!("{}/assets/{file}", env!("CARGO_MANIFEST_DIR"));
+    let loaded: T = config::load(&path).unwrap();
+    assert_eq!(loaded, T::default());
+}
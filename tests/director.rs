@@ -0,0 +1,144 @@
+use nannou::prelude::*;
+use survivor::config;
+use survivor::sim::{Archetypes, Director, Rng, Spawn, Tuning, Waves, TICK};
+
+fn arena() -> Rect {
+    Rect::from_w_h(1024.0, 768.0)
+}
+
+/// Runs the built-in schedule headlessly for `minutes`, returning the spawns
+/// of each minute.
+fn run(seed: u64, minutes: usize) -> Vec<Vec<Spawn>> {
+    let tuning = Tuning::default();
+    let mut director = Director::new(&tuning.waves);
+    let mut rng = Rng::new(seed);
+    let ticks_per_minute = (60.0 / TICK).round() as usize;
+    let mut time = 0.0;
+    (0..minutes)
+        .map(|_| {
+            let mut spawns = vec![];
+            for _ in 0..ticks_per_minute {
+                time += TICK;
+                spawns.extend(director.update(
+                    &tuning.waves,
+                    &tuning.archetypes,
+                    arena(),
+                    time,
+                    TICK,
+                    &mut rng,
+                ));
+            }
+            spawns
+        })
+        .collect()
+}
+
+#[test]
+fn ten_minute_run_spawn_counts() {
+    let counts: Vec<usize> = run(2024, 10).iter().map(Vec::len).collect();
+    assert_eq!(counts, [71, 158, 182, 210, 236, 283, 317, 339, 388, 450]);
+    assert!(counts.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn same_seed_same_schedule() {
+    assert_eq!(run(5, 3), run(5, 3));
+    assert_ne!(run(5, 3), run(6, 3));
+}
+
+#[test]
+fn spawn_rate_rises_over_the_run() {
+    let waves = Waves::default();
+    let rates: Vec<f32> = (0..10)
+        .map(|minute| waves.spawn_rate(minute as f32 * 60.0 + 30.0))
+        .collect();
+    assert!(rates.windows(2).all(|pair| pair[0] < pair[1]), "{rates:?}");
+}
+
+#[test]
+fn overtime_keeps_the_final_waves_running() {
+    let waves = Waves::default();
+    assert!(waves.spawn_rate(1200.0) > 0.0);
+    assert!(waves.current(1200.0).is_some());
+
+    let mut quiet = waves.clone();
+    quiet.overtime = false;
+    assert_eq!(quiet.spawn_rate(1200.0), 0.0);
+}
+
+#[test]
+fn spawns_start_outside_the_arena() {
+    for spawn in run(1, 10).concat() {
+        assert!(!arena().contains(spawn.position), "{spawn:?}");
+    }
+}
+
+const TWO_WAVES: &str = r#"
+[[wave]]
+name = "left line"
+start = 0.0
+end = 60.0
+interval = [1.0, 1.0]
+group = [3, 3]
+formation = "line"
+edges = ["left"]
+mix = { tank = 1.0 }
+
+[[wave]]
+name = "late"
+start = 30.0
+end = 60.0
+interval = [2.0, 2.0]
+group = [1, 1]
+"#;
+
+#[test]
+fn waves_respect_edges_mix_and_start_time() {
+    let waves: Waves = config::parse(TWO_WAVES).unwrap();
+    let archetypes = Archetypes::default();
+    let tank = archetypes.find("tank").unwrap();
+    let mut director = Director::new(&waves);
+    let mut rng = Rng::new(3);
+
+    let mut first_half = vec![];
+    let mut time = 0.0;
+    for _ in 0..(29.5 / TICK) as usize {
+        time += TICK;
+        first_half.extend(director.update(&waves, &archetypes, arena(), time, TICK, &mut rng));
+    }
+    assert_eq!(first_half.len(), 29 * 3);
+    for spawn in first_half {
+        assert_eq!(spawn.archetype, tank);
+        assert!(spawn.position.x < arena().left());
+    }
+}
+
+#[test]
+fn mix_must_name_known_archetypes() {
+    let tuning = Tuning {
+        waves: config::parse(&TWO_WAVES.replace("tank", "dragon")).unwrap(),
+        ..Tuning::default()
+    };
+    let text = toml::to_string(&tuning).unwrap();
+    let err = config::parse::<Tuning>(&text).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "wave `left line`: unknown archetype `dragon` in mix"
+    );
+}
+
+#[test]
+fn rejects_bad_schedules() {
+    let err = config::parse::<Waves>(&TWO_WAVES.replace("end = 60.0", "end = 0.0"))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.starts_with("wave `left line`: needs 0 <= start < end"),
+        "{err}"
+    );
+
+    let err = config::parse::<Waves>(&TWO_WAVES.replace("group = [3, 3]", "group = [3, 1]"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("group must be [min, max]"), "{err}");
+}
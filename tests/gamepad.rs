@@ -0,0 +1,100 @@
+use nannou::prelude::*;
+use survivor::config;
+use survivor::gamepad::{Gamepad, GamepadSettings, Sticks};
+use survivor::settings::Settings;
+use survivor::sim::{Input, World, TICK};
+
+/// Plays back a fixed script of stick positions, one per poll, then unplugs.
+struct FakeGamepad(Vec<Sticks>);
+
+impl Gamepad for FakeGamepad {
+    fn sticks(&mut self) -> Option<Sticks> {
+        (!self.0.is_empty()).then(|| self.0.remove(0))
+    }
+}
+
+fn settings(dead_zone: f32, sensitivity: f32) -> GamepadSettings {
+    GamepadSettings {
+        dead_zone,
+        sensitivity,
+    }
+}
+
+#[test]
+fn dead_zone_drops_drift_and_rescales_the_rest() {
+    let pad = settings(0.2, 1.0);
+    assert_eq!(pad.shape(vec2(0.1, -0.15)), Vec2::ZERO);
+    assert!((pad.shape(vec2(0.6, 0.0)) - vec2(0.5, 0.0)).length() < 1e-6);
+    assert!((pad.shape(vec2(0.0, -1.0)) - vec2(0.0, -1.0)).length() < 1e-6);
+    // Corners of a square stick gate are no faster than full tilt
+    assert!((pad.shape(vec2(1.0, 1.0)).length() - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn sensitivity_reaches_full_speed_sooner() {
+    let shaped = |sensitivity| settings(0.0, sensitivity).shape(vec2(0.4, 0.0)).x;
+    assert!((shaped(1.0) - 0.4).abs() < 1e-6);
+    assert!((shaped(2.0) - 0.8).abs() < 1e-6);
+    assert_eq!(shaped(4.0), 1.0);
+}
+
+#[test]
+fn sticks_add_to_the_tick_input() {
+    let pad = settings(0.1, 1.0);
+    let mut gamepad = FakeGamepad(vec![Sticks {
+        left: vec2(1.0, 0.0),
+        right: vec2(0.0, -1.0),
+    }]);
+    let mut input = Input {
+        movement: vec2(0.0, 1.0),
+        ..Input::default()
+    };
+    pad.apply(&mut gamepad, &mut input);
+    assert!((input.movement - vec2(1.0, 1.0).normalize()).length() < 1e-6);
+    assert_eq!(input.flick, vec2(0.0, -1.0));
+
+    // Unplugged: keyboard movement is left alone
+    let mut input = Input {
+        movement: vec2(0.0, 1.0),
+        ..Input::default()
+    };
+    pad.apply(&mut gamepad, &mut input);
+    assert_eq!(input.movement, vec2(0.0, 1.0));
+    assert_eq!(input.flick, Vec2::ZERO);
+}
+
+#[test]
+fn flicking_swings_the_rope_tip() {
+    let mut world = World::new(Rect::from_w_h(1024.0, 768.0), 1);
+    let mut gamepad = FakeGamepad(vec![
+        Sticks {
+            right: vec2(0.0, 1.0),
+            ..Sticks::default()
+        };
+        10
+    ]);
+    let pad = Settings::default().gamepad;
+    for _ in 0..10 {
+        let mut input = Input::default();
+        pad.apply(&mut gamepad, &mut input);
+        world.step(&input, TICK);
+    }
+    let tip = *world.rope.points.last().unwrap();
+    assert!(tip.y > 20.0, "tip at {tip}");
+    assert_eq!(world.rope.points[0], pt2(0.0, 0.0));
+}
+
+#[test]
+fn rejects_bad_stick_settings() {
+    let error = |assignment: &str| {
+        config::with_overrides(&Settings::default(), &[assignment.to_string()])
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(
+        error("gamepad = { dead_zone = 1.0, sensitivity = 1.0 }").contains("gamepad: dead_zone")
+    );
+    assert!(
+        error("gamepad = { dead_zone = 0.1, sensitivity = 0.0 }").contains("gamepad: sensitivity")
+    );
+}
@@ -0,0 +1,179 @@
+use nannou::prelude::*;
+use survivor::config;
+use survivor::input::{Action, Actions, Bindings, Button};
+use survivor::settings::Settings;
+
+fn bindings() -> Bindings {
+    Settings::default().bindings
+}
+
+fn key(key: Key) -> Button {
+    Button::Key(key)
+}
+
+/// Settings text with the given `[bindings]` table.
+fn with_bindings(bindings: &str) -> String {
+    format!("controls = \"both\"\n[gamepad]\ndead_zone = 0.1\nsensitivity = 1.0\n[bindings]\n{bindings}")
+}
+
+fn parse_error(bindings: &str) -> String {
+    config::parse::<Settings>(&with_bindings(bindings))
+        .unwrap_err()
+        .to_string()
+}
+
+const ALL_BOUND: &str = r#"
+move_up = ["W"]
+move_down = ["S"]
+move_left = ["A"]
+move_right = ["D"]
+drag = ["MouseLeft"]
+extend = ["WheelUp"]
+retract = ["WheelDown"]
+dash = ["Space"]
+pause = ["Escape"]
+"#;
+
+#[test]
+fn buttons_round_trip_through_their_names() {
+    for name in [
+        "W",
+        "Up",
+        "Key1",
+        "LShift",
+        "F2",
+        "MouseLeft",
+        "Mouse4",
+        "WheelDown",
+    ] {
+        let button: Button = name.parse().unwrap();
+        assert_eq!(button.to_string(), name);
+        assert!(button.is_bindable());
+    }
+    assert!("Jump".parse::<Button>().is_err());
+    assert!(!key(Key::Mail).is_bindable());
+}
+
+#[test]
+fn buttons_may_only_be_used_once() {
+    let twice = format!("{ALL_BOUND}restart = [\"Space\"]");
+    assert_eq!(
+        parse_error(&twice),
+        "bindings: Space is bound to both `dash` and `restart`"
+    );
+    let unknown = format!("{ALL_BOUND}restart = [\"Hyper\"]");
+    assert!(parse_error(&unknown).contains("unknown button `Hyper`"));
+
+    let unbound = format!("{ALL_BOUND}restart = []");
+    let settings: Settings = config::parse(&with_bindings(&unbound)).unwrap();
+    assert!(settings.bindings.buttons(Action::Restart).is_empty());
+}
+
+#[test]
+fn reserved_keys_cannot_be_bound() {
+    for name in ["F1", "Period", "Key1", "Key9"] {
+        assert!(name.parse::<Button>().unwrap().is_reserved(), "{name}");
+    }
+    assert!(!key(Key::Key0).is_reserved());
+    assert_eq!(
+        parse_error(&format!("{ALL_BOUND}restart = [\"F1\"]")),
+        "bindings: F1 is reserved and can't be bound to `restart`"
+    );
+    assert_eq!(
+        parse_error("dash = [\"Period\"]"),
+        "bindings: Period is reserved and can't be bound to `dash`"
+    );
+}
+
+#[test]
+fn actions_left_out_get_their_shipped_buttons_if_free() {
+    // As saved before `restart` existed, with its shipped R taken by dash
+    let text = ALL_BOUND.replace(r#"dash = ["Space"]"#, r#"dash = ["R"]"#);
+    let settings: Settings = config::parse(&with_bindings(&text)).unwrap();
+    assert!(settings.bindings.buttons(Action::Restart).is_empty());
+
+    let settings: Settings = config::parse(&with_bindings(ALL_BOUND)).unwrap();
+    assert_eq!(settings.bindings.buttons(Action::Restart), [key(Key::R)]);
+    assert_eq!(settings.bindings.buttons(Action::Dash), [key(Key::Space)]);
+}
+
+#[test]
+fn rebinding_takes_the_button_from_its_old_action() {
+    let mut bindings = bindings();
+    bindings.rebind(Action::Pause, key(Key::Space));
+    assert_eq!(bindings.buttons(Action::Pause), [key(Key::Space)]);
+    assert_eq!(bindings.action(key(Key::Space)), Some(Action::Pause));
+    assert!(!bindings.buttons(Action::Dash).contains(&key(Key::Space)));
+    assert_eq!(bindings.action(key(Key::Escape)), None);
+}
+
+#[test]
+fn held_actions_steer_and_drag() {
+    let bindings = bindings();
+    let mut actions = Actions::default();
+    actions.press(key(Key::W), &bindings);
+    actions.press(key(Key::Right), &bindings);
+    actions.press(Button::Mouse(MouseButton::Left), &bindings);
+    let input = actions.tick_input(pt2(5.0, 6.0));
+    assert!((input.movement - vec2(1.0, 1.0).normalize()).length() < 1e-6);
+    assert_eq!(input.drag_index, Some(0));
+    assert_eq!(input.cursor, pt2(5.0, 6.0));
+
+    // Opposite directions cancel out
+    actions.press(key(Key::S), &bindings);
+    actions.release(key(Key::Right), &bindings);
+    actions.release(Button::Mouse(MouseButton::Left), &bindings);
+    let input = actions.tick_input(pt2(0.0, 0.0));
+    assert_eq!(input.movement, Vec2::ZERO);
+    assert_eq!(input.drag_index, None);
+}
+
+#[test]
+fn actions_stay_held_until_every_button_for_them_is_released() {
+    let bindings = bindings();
+    let mut actions = Actions::default();
+    actions.press(Button::Mouse(MouseButton::Left), &bindings);
+    actions.press(Button::Mouse(MouseButton::Right), &bindings);
+    actions.release(Button::Mouse(MouseButton::Right), &bindings);
+    assert!(actions.is_held(Action::Drag));
+    actions.release(Button::Mouse(MouseButton::Left), &bindings);
+    assert!(!actions.is_held(Action::Drag));
+
+    actions.press(key(Key::W), &bindings);
+    actions.press(key(Key::Up), &bindings);
+    actions.release(key(Key::W), &bindings);
+    assert_eq!(actions.movement(), vec2(0.0, 1.0));
+}
+
+#[test]
+fn presses_are_counted_once_and_taken_by_the_next_tick() {
+    let bindings = bindings();
+    let mut actions = Actions::default();
+    for _ in 0..3 {
+        actions.press(Button::WheelUp, &bindings);
+    }
+    // Key repeat while held is not another press
+    actions.press(key(Key::Q), &bindings);
+    actions.press(key(Key::Q), &bindings);
+    assert_eq!(actions.tick_input(Point2::ZERO).extend, 2);
+    assert_eq!(actions.tick_input(Point2::ZERO).extend, 0);
+
+    actions.release(key(Key::Q), &bindings);
+    actions.press(key(Key::Q), &bindings);
+    assert_eq!(actions.tick_input(Point2::ZERO).extend, -1);
+
+    actions.press(key(Key::Space), &bindings);
+    assert!(actions.tick_input(Point2::ZERO).dash);
+    assert!(!actions.tick_input(Point2::ZERO).dash);
+}
+
+#[test]
+fn saved_settings_load_back_the_same() {
+    let mut settings = Settings::default();
+    settings.bindings.rebind(Action::Dash, key(Key::LShift));
+    let path = std::env::temp_dir().join(format!("survivor-settings-{}.toml", std::process::id()));
+    settings.save(&path).unwrap();
+    let loaded: Settings = config::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, settings);
+}
@@ -0,0 +1,152 @@
+use nannou::prelude::*;
+use survivor::replay::{Player, Recorder, Replay, MAX_SPEED, MIN_SPEED};
+use survivor::sim::{Input, Orb, World, TICK};
+use survivor::state::State;
+
+/// Records `ticks` ticks of a scripted drag circling the arena centre, steered
+/// back between drags, now and then growing, shrinking or flicking the rope
+/// or dashing, and taking the first upgrade offered.
+fn record(seed: u64, ticks: u64) -> (Replay, u64) {
+    let mut world = World::new(Rect::from_w_h(800.0, 600.0), seed);
+    let mut recorder = Recorder::new(&world);
+    for tick in 0..ticks {
+        let angle = tick as f32 * 0.05;
+        let dragging = tick % 200 < 150;
+        let input = Input {
+            drag_index: dragging.then_some(0),
+            cursor: pt2(angle.cos(), angle.sin()) * 150.0,
+            extend: match tick % 90 {
+                0 => 2,
+                45 => -1,
+                _ => 0,
+            },
+            movement: if dragging {
+                Vec2::ZERO
+            } else {
+                vec2(-angle.sin(), angle.cos())
+            },
+            flick: if tick % 120 < 10 {
+                vec2(angle.sin(), 0.5)
+            } else {
+                Vec2::ZERO
+            },
+            dash: tick % 150 == 100,
+            upgrade: world.is_leveling_up().then_some(0),
+        };
+        if tick == ticks / 2 {
+            world.arena = Rect::from_w_h(1000.0, 700.0);
+        }
+        world.step(&input, TICK);
+        recorder.record(&input, &world);
+    }
+    let checksum = world.checksum();
+    (recorder.finish(&world), checksum)
+}
+
+fn round_trip(replay: &Replay) -> Replay {
+    let mut bytes = vec![];
+    replay.write_to(&mut bytes).unwrap();
+    Replay::read_from(&mut bytes.as_slice()).unwrap()
+}
+
+#[test]
+fn file_round_trip_is_lossless() {
+    let (replay, _) = record(7, 500);
+    assert_eq!(round_trip(&replay), replay);
+}
+
+#[test]
+fn playback_reproduces_the_recorded_run() {
+    let (replay, checksum) = record(7, 900);
+    let mut player = Player::new(round_trip(&replay));
+    while !player.is_finished() {
+        player.step().unwrap();
+    }
+    assert_eq!(player.world().checksum(), checksum);
+}
+
+#[test]
+fn picking_an_upgrade_resumes_a_live_run() {
+    let mut world = World::new(Rect::from_w_h(800.0, 600.0), 5);
+    let mut recorder = Recorder::new(&world);
+    world.xp = world.xp_to_next_level() - 1;
+    world.orbs.push(Orb::new(world.rope.points[0], 1));
+    recorder.advance(&mut world, Input::default(), 3);
+    assert!(world.is_leveling_up());
+    assert_eq!(world.tick, 1);
+    assert_eq!(State::Playing.after_step(&world), State::LevelUp);
+
+    // Nothing picked, or a pick that isn't on offer, holds the world still
+    recorder.advance(&mut world, Input::default(), 3);
+    let out_of_range = Input {
+        upgrade: Some(world.choices.len() as u8),
+        ..Input::default()
+    };
+    recorder.advance(&mut world, out_of_range, 3);
+    assert_eq!(world.tick, 1);
+
+    // As the frontend does once a pick key is pressed
+    let pick = Input {
+        upgrade: Some(0),
+        ..Input::default()
+    };
+    recorder.advance(&mut world, pick, 3);
+    assert!(!world.is_leveling_up());
+    assert_eq!(world.tick, 4);
+    assert_eq!(world.taken.iter().sum::<u32>(), 1);
+    assert_eq!(State::Playing.after_step(&world), State::Playing);
+    assert_eq!(recorder.finish(&world).frames.len(), 4);
+}
+
+#[test]
+fn speed_scales_playback_and_is_clamped() {
+    let (replay, _) = record(3, 600);
+    let mut player = Player::new(replay);
+    player.set_speed(4.0);
+    player.advance(0.5).unwrap();
+    assert!(player.world().tick >= 8);
+
+    player.set_speed(100.0);
+    assert_eq!(player.speed(), MAX_SPEED);
+    player.set_speed(0.0);
+    assert_eq!(player.speed(), MIN_SPEED);
+}
+
+#[test]
+fn paused_player_does_not_advance() {
+    let (replay, _) = record(3, 120);
+    let mut player = Player::new(replay);
+    player.paused = true;
+    player.advance(1.0).unwrap();
+    assert_eq!(player.world().tick, 0);
+}
+
+#[test]
+fn seeking_backwards_resimulates_to_the_same_state() {
+    let (replay, _) = record(11, 600);
+    let mut player = Player::new(replay);
+    player.seek(400).unwrap();
+    let at_400 = player.world().checksum();
+    player.seek(100).unwrap();
+    assert_eq!(player.world().tick, 100);
+    player.seek(400).unwrap();
+    assert_eq!(player.world().checksum(), at_400);
+}
+
+#[test]
+fn tampered_input_reports_divergence() {
+    let (mut replay, _) = record(5, 300);
+    replay.frames[10].input.cursor += vec2(40.0, 0.0);
+    replay.frames[10].input.drag_index = Some(0);
+    let mut player = Player::new(replay);
+    let divergence = player.seek(300).unwrap_err();
+    assert_eq!(divergence.tick, 60);
+    assert_ne!(divergence.expected, divergence.actual);
+    assert!(divergence.to_string().contains("diverged at tick 60"));
+}
+
+#[test]
+fn rejects_files_that_are_not_replays() {
+    let err = Replay::read_from(&mut &b"nope, not a replay"[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
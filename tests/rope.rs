@@ -0,0 +1,351 @@
+use std::f32::consts::TAU;
+
+use nannou::prelude::*;
+use survivor::config;
+use survivor::sim::{Rope, RopeParams, Solver};
+
+/// One tick's worth of substeps.
+const DELTA_TIME: f32 = 1.0 / 60.0 / 5.0;
+
+fn simulate(params: RopeParams, seconds: f32) -> Rope {
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+    for _ in 0..(seconds / DELTA_TIME) as usize {
+        rope.update(DELTA_TIME);
+    }
+    rope
+}
+
+fn overrides(assignments: &[&str]) -> Result<RopeParams, String> {
+    let assignments: Vec<String> = assignments.iter().map(|a| a.to_string()).collect();
+    config::with_overrides(&RopeParams::default(), &assignments).map_err(|err| err.to_string())
+}
+
+#[test]
+fn segments_set_the_point_count() {
+    let params = RopeParams {
+        segments: 20,
+        ..RopeParams::default()
+    };
+    let rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+    assert_eq!(rope.points.len(), 21);
+    assert_eq!(rope.segment_length, 5.0);
+}
+
+#[test]
+fn gravity_pulls_the_tail_down() {
+    let params = RopeParams {
+        gravity: [0.0, -400.0],
+        ..RopeParams::default()
+    };
+    let rope = simulate(params, 3.0);
+    let tail = *rope.points.last().unwrap();
+    // Hanging from the pinned head, roughly at full length
+    assert!(tail.y < -80.0, "tail at {tail}");
+    assert!(tail.x.abs() < 20.0, "tail at {tail}");
+}
+
+#[test]
+fn damping_slows_a_swinging_rope() {
+    let swing = |damping| {
+        let params = RopeParams {
+            damping,
+            ..RopeParams::default()
+        };
+        let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+        for prev in rope.prev_points.iter_mut().skip(1) {
+            prev.y -= 2.0;
+        }
+        for _ in 0..60 {
+            rope.update(DELTA_TIME);
+        }
+        let tail = rope.points.len() - 1;
+        rope.points[tail].distance(rope.prev_points[tail])
+    };
+    assert!(swing(10.0) < swing(0.0) * 0.9);
+}
+
+#[test]
+fn overrides_replace_single_values() {
+    let params = overrides(&["gravity = [0, -400]", "iterations=4"]).unwrap();
+    assert_eq!(params.gravity, [0.0, -400.0]);
+    assert_eq!(params.iterations, 4);
+    assert_eq!(params.thickness, RopeParams::default().thickness);
+}
+
+#[test]
+fn rejects_bad_overrides() {
+    assert!(overrides(&["damping"])
+        .unwrap_err()
+        .contains("override `damping`"));
+    assert!(overrides(&["springiness = 2.0"])
+        .unwrap_err()
+        .contains("springiness"));
+    assert_eq!(
+        overrides(&["solver = { kind = \"relaxation\", stiffness = 1.5 }"]).unwrap_err(),
+        "rope: stiffness must be above 0 and at most 1 (got 1.5)"
+    );
+    assert_eq!(
+        overrides(&["segments = 0"]).unwrap_err(),
+        "rope: segments must be at least 1"
+    );
+}
+
+/// Total length relative to rest length, minus one.
+fn stretch(rope: &Rope) -> f32 {
+    let length: f32 = rope.points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let rest = rope.segment_length * (rope.points.len() - 1) as f32;
+    length / rest - 1.0
+}
+
+/// Hangs a rope under gravity, stepping `substeps` times per 60 Hz tick.
+fn hang(solver: Solver, iterations: u32, substeps: u32) -> Rope {
+    let params = RopeParams {
+        gravity: [0.0, -400.0],
+        solver,
+        iterations,
+        ..RopeParams::default()
+    };
+    let delta_time = 1.0 / 60.0 / substeps as f32;
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+    for _ in 0..(5.0 / delta_time) as usize {
+        rope.update(delta_time);
+    }
+    rope
+}
+
+/// Swings the head in a fast circle and returns the worst stretch seen at the
+/// end of any tick.
+fn whip(solver: Solver) -> f32 {
+    let params = RopeParams {
+        solver,
+        ..RopeParams::default()
+    };
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+    let mut worst: f32 = 0.0;
+    for tick in 0..180 {
+        for substep in 0..5 {
+            let time = (tick * 5 + substep) as f32 * DELTA_TIME;
+            let angle = time * 2.0 * TAU;
+            rope.update(DELTA_TIME);
+            rope.points[0] = pt2(angle.cos(), angle.sin()) * 100.0;
+        }
+        worst = worst.max(stretch(&rope).abs());
+    }
+    worst
+}
+
+#[test]
+fn xpbd_stretch_is_the_same_at_any_iteration_or_substep_count() {
+    let solver = Solver::Xpbd { compliance: 1e-4 };
+    let reference = stretch(&hang(solver, 15, 5));
+    assert!(reference > 0.01, "barely stretched: {reference}");
+    for (iterations, substeps) in [(4, 2), (30, 10)] {
+        let other = stretch(&hang(solver, iterations, substeps));
+        assert!(
+            (other / reference - 1.0).abs() < 0.05,
+            "{iterations} iterations, {substeps} substeps: {other} vs {reference}"
+        );
+    }
+}
+
+#[test]
+fn relaxation_stretch_depends_on_iteration_and_substep_count() {
+    let solver = Solver::Relaxation { stiffness: 0.133 };
+    let loose = stretch(&hang(solver, 4, 2));
+    let tight = stretch(&hang(solver, 30, 10));
+    assert!(loose > tight * 10.0, "{loose} vs {tight}");
+}
+
+#[test]
+fn rigid_xpbd_holds_its_length_while_whipped() {
+    let xpbd = whip(Solver::Xpbd { compliance: 0.0 });
+    let relaxation = whip(Solver::Relaxation { stiffness: 0.133 });
+    assert!(xpbd < 0.1, "stretched by {xpbd}");
+    assert!(xpbd < relaxation / 4.0, "{xpbd} vs {relaxation}");
+}
+
+#[test]
+fn pinned_points_stay_put() {
+    let params = RopeParams {
+        gravity: [0.0, -400.0],
+        ..RopeParams::default()
+    };
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+    let pinned = rope.points[6];
+    rope.inverse_masses[6] = 0.0;
+    for _ in 0..300 {
+        rope.update(DELTA_TIME);
+    }
+    assert_eq!(rope.points[6], pinned);
+    assert!(rope.points[3].y < -1.0 && rope.points[9].y < -1.0);
+}
+
+#[test]
+fn only_overstretched_segments_tear() {
+    let params = RopeParams {
+        tear_stretch: Some(1.0),
+        ..RopeParams::default()
+    };
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+    for point in rope.points[4..].iter_mut() {
+        point.x += 20.0;
+    }
+    assert_eq!(rope.tear_overstretched().len(), 1);
+    assert_eq!(rope.links[3], None);
+    assert_eq!(rope.attached_len(), 4);
+    assert!(rope.links.iter().filter(|link| link.is_some()).count() == 10);
+
+    let unbreakable = RopeParams {
+        tear_stretch: None,
+        ..RopeParams::default()
+    };
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), unbreakable);
+    rope.points[11].x += 1000.0;
+    assert!(rope.tear_overstretched().is_empty());
+}
+
+#[test]
+fn picking_up_rejoins_every_piece_up_to_the_one_touched() {
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), RopeParams::default());
+    rope.tear(2);
+    rope.tear(7);
+    for point in rope.points[3..].iter_mut() {
+        *point += vec2(0.0, 50.0);
+    }
+    assert!(!rope.pick_up());
+
+    rope.points[0] = rope.points[9];
+    assert!(rope.pick_up());
+    assert_eq!(rope.attached_len(), rope.points.len());
+    // Rejoined at the length they were touched at
+    assert!(rope.links[2].unwrap() > rope.segment_length);
+}
+
+/// A rope whose points after the head are all drifting upward.
+fn drifting_rope(params: RopeParams) -> Rope {
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+    for prev in rope.prev_points.iter_mut().skip(1) {
+        prev.y -= 1.0;
+    }
+    rope
+}
+
+#[test]
+fn extending_adds_points_at_the_head_and_keeps_the_chain_moving() {
+    let mut rope = drifting_rope(RopeParams::default());
+    let tail = rope.points[1..].to_vec();
+    let tail_prev = rope.prev_points[1..].to_vec();
+
+    assert!(rope.extend());
+    assert_eq!(rope.points.len(), 13);
+    assert_eq!(rope.links.len(), 12);
+    assert_eq!(rope.points[2..], tail[..]);
+    assert_eq!(rope.prev_points[2..], tail_prev[..]);
+    // The new point moves with its neighbours
+    assert_eq!(rope.points[1] - rope.prev_points[1], vec2(0.0, 0.5));
+
+    // Both halves of the split segment grow back to full length
+    for _ in 0..300 {
+        rope.update(DELTA_TIME);
+    }
+    assert!(rope
+        .links
+        .iter()
+        .all(|rest| *rest == Some(rope.segment_length)));
+    let length: f32 = rope.points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    assert!((length / (rope.segment_length * 12.0) - 1.0).abs() < 0.02);
+}
+
+#[test]
+fn extending_stops_at_the_maximum() {
+    let params = RopeParams {
+        max_segments: 13,
+        ..RopeParams::default()
+    };
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(100.0, 0.0), params);
+    assert!(rope.extend());
+    assert!(rope.extend());
+    assert!(!rope.extend());
+    assert_eq!(rope.links.len(), 13);
+}
+
+#[test]
+fn retracting_removes_points_at_the_head_and_keeps_the_chain_moving() {
+    let mut rope = drifting_rope(RopeParams::default());
+    let tail = rope.points[2..].to_vec();
+    let tail_prev = rope.prev_points[2..].to_vec();
+
+    assert!(rope.retract());
+    assert_eq!(rope.points.len(), 11);
+    assert_eq!(rope.points[1..], tail[..]);
+    assert_eq!(rope.prev_points[1..], tail_prev[..]);
+    // Joined at its current length so nothing is yanked
+    assert_eq!(rope.links[0], Some(rope.points[0].distance(rope.points[1])));
+
+    while rope.retract() {}
+    assert_eq!(rope.points.len(), 2);
+    assert_eq!(rope.inverse_masses, [0.0, 1.0 / rope.params.tip_mass]);
+}
+
+/// A rope folded back on itself with the two halves 1 px apart, at rest.
+fn hairpin(self_collision: bool) -> Rope {
+    let params = RopeParams {
+        segments: 20,
+        self_collision,
+        ..RopeParams::default()
+    };
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(200.0, 0.0), params);
+    for (i, point) in rope.points.iter_mut().enumerate() {
+        *point = if i <= 10 {
+            pt2(i as f32 * 10.0, 0.0)
+        } else {
+            pt2(200.0 - i as f32 * 10.0, 1.0)
+        };
+    }
+    rope.prev_points = rope.points.clone();
+    for _ in 0..30 {
+        rope.update(DELTA_TIME);
+    }
+    rope
+}
+
+/// Closest the returning half of a hairpin comes to the outgoing half.
+fn fold_gap(rope: &Rope) -> f32 {
+    (13..=18)
+        .flat_map(|k| (0..10).map(move |i| (k, i)))
+        .map(|(k, i)| {
+            let (a, b, p) = (rope.points[i], rope.points[i + 1], rope.points[k]);
+            let t = ((p - a).dot(b - a) / (b - a).length_squared()).clamp(0.0, 1.0);
+            p.distance(a.lerp(b, t))
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[test]
+fn self_collision_keeps_a_folded_rope_apart() {
+    let gap = RopeParams::default().thickness * 2.0;
+    let apart = fold_gap(&hairpin(true));
+    assert!(apart > gap * 0.9, "halves {apart} apart");
+    let overlapping = fold_gap(&hairpin(false));
+    assert!(overlapping < 2.0, "halves {overlapping} apart");
+}
+
+#[test]
+fn winding_counts_loops_around_a_point() {
+    let params = RopeParams {
+        segments: 24,
+        ..RopeParams::default()
+    };
+    let mut rope = Rope::new(pt2(0.0, 0.0), pt2(240.0, 0.0), params);
+    // A loop and a quarter, counter-clockwise around the origin
+    for (i, point) in rope.points.iter_mut().enumerate() {
+        let angle = i as f32 / 24.0 * 1.25 * TAU;
+        *point = vec2(angle.cos(), angle.sin()) * 40.0;
+    }
+    assert!((rope.winding(pt2(0.0, 0.0)) - 1.25).abs() < 1e-3);
+    assert!(rope.winding(pt2(200.0, 0.0)).abs() < 0.5);
+
+    // Only the part still attached to the head counts
+    rope.tear(12);
+    assert!((rope.winding(pt2(0.0, 0.0)) - 0.625).abs() < 1e-3);
+}
@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::PathBuf;
+
+use nannou::prelude::*;
+use survivor::scores::{self, Entry, HighScores, MAX_ENTRIES, PATH_VAR};
+use survivor::sim::World;
+
+fn entry(score: i32, survived: f32) -> Entry {
+    Entry {
+        score,
+        survived,
+        seed: 1,
+        date: "2026-10-18".into(),
+        version: "0.1.0".into(),
+    }
+}
+
+/// A fresh path in a directory of its own, which the caller removes.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("survivor-scores-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir.join("nested").join("scores.toml")
+}
+
+#[test]
+fn keeps_the_best_runs_in_order() {
+    let mut table = HighScores::default();
+    assert_eq!(table.insert(entry(10, 30.0)), Some(0));
+    assert_eq!(table.insert(entry(20, 10.0)), Some(0));
+    // Same score, longer run ranks higher; a full tie goes to the older one
+    assert_eq!(table.insert(entry(10, 40.0)), Some(1));
+    assert_eq!(table.insert(entry(10, 40.0)), Some(2));
+    let scores: Vec<_> = table
+        .entries()
+        .iter()
+        .map(|entry| (entry.score, entry.survived))
+        .collect();
+    assert_eq!(scores, [(20, 10.0), (10, 40.0), (10, 40.0), (10, 30.0)]);
+
+    for score in 100..100 + MAX_ENTRIES as i32 {
+        table.insert(entry(score, 1.0));
+    }
+    assert_eq!(table.entries().len(), MAX_ENTRIES);
+    assert_eq!(table.insert(entry(0, 0.0)), None);
+    assert_eq!(table.entries()[0].score, 100 + MAX_ENTRIES as i32 - 1);
+}
+
+#[test]
+fn saved_table_loads_back_the_same() {
+    let path = scratch("round-trip");
+    let (table, error) = HighScores::load(&path);
+    assert!(table.entries().is_empty());
+    assert!(error.is_none(), "a missing file is not an error");
+
+    let mut table = HighScores::default();
+    table.insert(entry(5, 12.5));
+    let mut world = World::new(Rect::from_w_h(800.0, 600.0), 42);
+    world.score = 7;
+    world.elapsed = 3.0;
+    table.insert(Entry::new(&world));
+    table.save(&path).unwrap();
+
+    let (loaded, error) = HighScores::load(&path);
+    fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    assert!(error.is_none());
+    assert_eq!(loaded, table);
+    assert_eq!(loaded.entries()[0].seed, 42);
+    assert_eq!(loaded.entries()[0].version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn corrupt_files_are_moved_aside_and_start_a_new_table() {
+    for (name, bytes) in [
+        ("garbled", b"[[entries]]\nscore = \"lots\"".as_slice()),
+        (
+            "truncated",
+            b"[[entries]]\nscore = 3\nsurvived = 1".as_slice(),
+        ),
+        ("binary", b"\xff\xfe\x00scores".as_slice()),
+        (
+            "nonsense",
+            b"[[entries]]\nscore = 1\nsurvived = -4.0\nseed = 1\ndate = \"\"\nversion = \"\""
+                .as_slice(),
+        ),
+    ] {
+        let path = scratch(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, bytes).unwrap();
+
+        let (table, error) = HighScores::load(&path);
+        assert!(table.entries().is_empty(), "{name}");
+        assert!(error.is_some(), "{name}");
+        let backup = path.with_extension("toml.bad");
+        assert_eq!(fs::read(&backup).unwrap(), bytes, "{name}");
+        assert!(!path.exists(), "{name}");
+
+        table.save(&path).unwrap();
+        assert_eq!(HighScores::load(&path).0, table);
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn earlier_backups_are_kept() {
+    let path = scratch("twice");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    for garbage in ["first", "second", "third"] {
+        fs::write(&path, garbage).unwrap();
+        assert!(HighScores::load(&path).1.is_some());
+    }
+    assert_eq!(
+        fs::read_to_string(path.with_extension("toml.bad")).unwrap(),
+        "first"
+    );
+    assert_eq!(
+        fs::read_to_string(path.with_extension("toml.bad.1")).unwrap(),
+        "second"
+    );
+    assert_eq!(
+        fs::read_to_string(path.with_extension("toml.bad.2")).unwrap(),
+        "third"
+    );
+    fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+}
+
+#[test]
+fn hand_edited_tables_are_put_in_order() {
+    let path = scratch("hand-edited");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut text = String::new();
+    for score in 0..MAX_ENTRIES as i32 + 5 {
+        text += &format!(
+            "[[entries]]\nscore = {score}\nsurvived = 1.0\nseed = 1\ndate = \"2026-01-01\"\nversion = \"0.0.1\"\n"
+        );
+    }
+    fs::write(&path, text).unwrap();
+    let (table, error) = HighScores::load(&path);
+    fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    assert!(error.is_none());
+    assert_eq!(table.entries().len(), MAX_ENTRIES);
+    assert_eq!(table.entries()[0].score, MAX_ENTRIES as i32 + 4);
+}
+
+#[test]
+fn environment_variable_overrides_the_data_directory() {
+    std::env::set_var(PATH_VAR, "/tmp/my-scores.toml");
+    assert_eq!(scores::path(), Some(PathBuf::from("/tmp/my-scores.toml")));
+    std::env::remove_var(PATH_VAR);
+    if let Some(path) = scores::path() {
+        assert!(path.ends_with("survivor/scores.toml"), "{}", path.display());
+    }
+}
+
+#[test]
+fn dates_are_utc_days() {
+    assert_eq!(scores::date(0), "1970-01-01");
+    assert_eq!(scores::date(951_782_400), "2000-02-29");
+    assert_eq!(scores::date(1_792_281_600), "2026-10-18");
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use survivor::config;
+use survivor::settings::{Controls, Settings};
+
+#[test]
+fn shipped_file_matches_builtin() {
+    common::assert_shipped_matches_builtin::<Settings>("settings.toml");
+}
+
+#[test]
+fn controls_pick_which_devices_move_the_head() {
+    let controls = |name: &str| {
+        let assignment = format!("controls = \"{name}\"");
+        config::with_overrides(&Settings::default(), &[assignment]).map(|s| s.controls)
+    };
+    let (mouse, keyboard, both) = (
+        controls("mouse").unwrap(),
+        controls("keyboard").unwrap(),
+        controls("both").unwrap(),
+    );
+    assert_eq!(mouse, Controls::Mouse);
+    assert!(mouse.mouse() && !mouse.keyboard());
+    assert!(!keyboard.mouse() && keyboard.keyboard());
+    assert!(both.mouse() && both.keyboard());
+    assert!(controls("joystick").is_err());
+}
+
+#[test]
+fn files_without_bindings_use_the_shipped_ones() {
+    let settings: Settings = config::parse("controls = \"keyboard\"").unwrap();
+    assert_eq!(settings.controls, Controls::Keyboard);
+    assert_eq!(settings.bindings, Settings::default().bindings);
+}
+
+#[test]
+fn files_without_a_gamepad_table_use_the_shipped_one() {
+    let mut text = toml::to_string(&Settings::default()).unwrap();
+    let start = text.find("[gamepad]").unwrap();
+    let end = text.find("[bindings]").unwrap();
+    text.replace_range(start..end, "");
+    let loaded: Settings = config::parse(&text).unwrap();
+    assert_eq!(loaded.gamepad, Settings::default().gamepad);
+}
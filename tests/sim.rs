@@ -0,0 +1,484 @@
+use std::f32::consts::TAU;
+
+use nannou::prelude::*;
+use survivor::sim::{Enemy, Event, FixedTimestep, Input, Orb, World, TICK};
+
+fn arena() -> Rect {
+    Rect::from_w_h(1024.0, 768.0)
+}
+
+#[test]
+fn steps_without_a_window() {
+    let mut world = World::new(arena(), 1);
+    let input = Input::default();
+    for _ in 0..200 {
+        world.step(&input, TICK);
+    }
+    assert!(!world.enemies.is_empty());
+}
+
+#[test]
+fn dragging_moves_the_head_toward_the_cursor() {
+    let mut world = World::new(arena(), 1);
+    let input = Input {
+        drag_index: Some(0),
+        cursor: pt2(200.0, 100.0),
+        ..Input::default()
+    };
+    let before = world.rope.points[0].distance(input.cursor);
+    for _ in 0..10 {
+        world.step(&input, TICK);
+    }
+    assert!(world.rope.points[0].distance(input.cursor) < before);
+}
+
+#[test]
+fn dragging_past_the_end_of_the_rope_is_ignored() {
+    let mut world = World::new(arena(), 1);
+    let mut undragged = World::new(arena(), 1);
+    let input = Input {
+        drag_index: Some(world.rope.points.len()),
+        cursor: pt2(200.0, 100.0),
+        ..Input::default()
+    };
+    for _ in 0..30 {
+        world.step(&input, TICK);
+        undragged.step(&Input::default(), TICK);
+    }
+    assert_eq!(world.checksum(), undragged.checksum());
+}
+
+#[test]
+fn timestep_ticks_match_real_time_at_any_frame_rate() {
+    for hz in [30.0, 60.0, 144.0, 240.0] {
+        let mut timestep = FixedTimestep::new(TICK);
+        let mut ticks = 0;
+        for _ in 0..(hz as usize * 2) {
+            ticks += timestep.advance(1.0 / hz);
+        }
+        assert!((119..=120).contains(&ticks), "{hz} Hz ran {ticks} ticks");
+        assert!((0.0..1.0).contains(&timestep.alpha()));
+    }
+}
+
+#[test]
+fn timestep_drops_backlog_after_a_stall() {
+    let mut timestep = FixedTimestep::new(TICK);
+    assert!(timestep.advance(5.0) < 10);
+    assert!(timestep.alpha() < 1.0);
+}
+
+#[test]
+fn first_spawn_waits_one_wave_interval() {
+    let mut world = World::new(arena(), 1);
+    let input = Input::default();
+    let first = &world.tuning.waves.waves[0];
+    let ticks = (first.interval[0] / TICK).ceil() as usize;
+    for _ in 0..ticks - 1 {
+        world.step(&input, TICK);
+    }
+    assert!(world.enemies.is_empty());
+    world.step(&input, TICK);
+    world.step(&input, TICK);
+    assert_eq!(world.enemies.len(), 1);
+}
+
+#[test]
+fn same_seed_spawns_the_same_enemies() {
+    let run = |seed| {
+        let mut world = World::new(arena(), seed);
+        for _ in 0..300 {
+            world.step(&Input::default(), TICK);
+        }
+        world
+            .enemies
+            .iter()
+            .map(|enemy| (enemy.position, enemy.radius, enemy.color))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(42), run(42));
+    assert_ne!(run(42), run(43));
+}
+
+#[test]
+fn touching_the_head_costs_health_once_per_invulnerability_window() {
+    let mut world = World::new(arena(), 1);
+    let head = world.rope.points[0] + vec2(0.0, 10.0);
+    world
+        .enemies
+        .push(Enemy::new(head, 15.0, Rgba::new(1.0, 0.0, 0.0, 1.0), 30.0));
+    world.step(&Input::default(), TICK);
+    let after_hit = world.health.hp;
+    assert_eq!(after_hit, world.tuning.max_hp - world.tuning.contact_damage);
+    assert!(world.health.is_invulnerable());
+
+    world.enemies[0].position = world.rope.points[0] + vec2(0.0, 10.0);
+    world.step(&Input::default(), TICK);
+    assert_eq!(world.health.hp, after_hit);
+}
+
+#[test]
+fn game_over_freezes_the_world() {
+    let mut world = World::new(arena(), 1);
+    world.health.hp = 0.0;
+    assert!(world.is_game_over());
+    let checksum = world.checksum();
+    for _ in 0..100 {
+        world.step(&Input::default(), TICK);
+    }
+    assert_eq!(world.checksum(), checksum);
+    assert_eq!(world.elapsed, 0.0);
+}
+
+#[test]
+fn idle_player_eventually_dies() {
+    let mut world = World::new(arena(), 1);
+    for _ in 0..60 * 300 {
+        world.step(&Input::default(), TICK);
+    }
+    assert!(world.is_game_over());
+}
+
+/// Swings the whole rope upward at `speed` px per substep into an enemy
+/// sitting just above the tail, and returns the world after one tick.
+fn whip(speed: f32, hp: f32) -> World {
+    let mut world = World::new(arena(), 1);
+    let tail = *world.rope.points.last().unwrap();
+    world.enemies.push(Enemy::new(
+        tail + vec2(-5.0, 18.0),
+        15.0,
+        Rgba::new(1.0, 0.0, 0.0, 1.0),
+        hp,
+    ));
+    for (point, prev) in world
+        .rope
+        .points
+        .iter()
+        .zip(world.rope.prev_points.iter_mut())
+    {
+        *prev = *point - vec2(0.0, speed);
+    }
+    world.step(&Input::default(), TICK);
+    world
+}
+
+#[test]
+fn faster_hits_deal_more_damage() {
+    let slow = whip(3.0, 1000.0);
+    let fast = whip(8.0, 1000.0);
+    let damage = |world: &World| world.enemies[0].max_hp - world.enemies[0].hp;
+    assert!(damage(&slow) > 0.0);
+    assert!(damage(&fast) > damage(&slow));
+    assert!(fast
+        .events
+        .iter()
+        .any(|event| matches!(event, Event::Damage { .. })));
+}
+
+#[test]
+fn resting_contact_does_not_damage() {
+    let world = whip(0.0, 1000.0);
+    assert_eq!(world.enemies[0].hp, 1000.0);
+}
+
+#[test]
+fn kills_award_score() {
+    let world = whip(8.0, 1.0);
+    assert!(world.enemies.is_empty());
+    assert_eq!(world.score, 1);
+    assert!(world
+        .events
+        .iter()
+        .any(|event| matches!(event, Event::Kill { .. })));
+}
+
+/// Steps with the head dragged toward `cursor` until `done` or `seconds` pass,
+/// keeping enemies out of the way.
+fn drag_until(world: &mut World, cursor: Point2, seconds: f32, done: impl Fn(&World) -> bool) {
+    let input = Input {
+        drag_index: Some(0),
+        cursor,
+        ..Input::default()
+    };
+    for _ in 0..(seconds / TICK) as usize {
+        world.enemies.clear();
+        world.step(&input, TICK);
+        if done(world) {
+            return;
+        }
+    }
+}
+
+#[test]
+fn pinned_rope_tears_and_is_picked_back_up() {
+    let mut world = World::new(arena(), 1);
+    let count = world.rope.points.len();
+    // As if a huge enemy were sitting on it
+    world.rope.inverse_masses[6] = 0.0;
+    drag_until(&mut world, pt2(-400.0, 0.0), 2.0, |world| {
+        world.rope.attached_len() < count
+    });
+    assert!(world
+        .events
+        .iter()
+        .any(|event| matches!(event, Event::Tear { .. })));
+    let attached = world.rope.attached_len();
+    assert!(attached <= 7, "tore at {attached}");
+
+    // The loose piece keeps simulating as a chain of its own
+    world.rope.inverse_masses[6] = 1.0;
+    let loose = world.rope.points[attached..].to_vec();
+    drag_until(&mut world, pt2(-400.0, 350.0), 0.5, |_| false);
+    assert_ne!(world.rope.points[attached..], loose[..]);
+    assert!(world.rope.attached_len() < count);
+
+    // Touching it with the head joins it back on and reels it in
+    let tip = *world.rope.points.last().unwrap();
+    drag_until(&mut world, tip, 2.0, |world| {
+        world.rope.attached_len() == count
+    });
+    assert_eq!(world.rope.attached_len(), count);
+    let head = world.rope.points[0];
+    let reeled_in = |world: &World| {
+        let rest = Some(world.rope.segment_length);
+        world.rope.links.iter().all(|link| *link == rest)
+    };
+    drag_until(&mut world, head, 20.0, reeled_in);
+    assert!(reeled_in(&world), "{:?}", world.rope.links);
+}
+
+/// A world with the rope laid in a loop around one small enemy and another
+/// enemy off to the side, stepped once.
+fn loop_around_enemy(turns: f32) -> World {
+    let mut world = World::new(arena(), 1);
+    let centre = pt2(0.0, 200.0);
+    let segments = world.rope.links.len() as f32;
+    // Sized so every segment keeps its rest length
+    let angle_step = turns * TAU / segments;
+    let radius = world.rope.segment_length / 2.0 / (angle_step / 2.0).sin();
+    for (i, point) in world.rope.points.iter_mut().enumerate() {
+        let angle = i as f32 * angle_step;
+        *point = centre + vec2(angle.cos(), angle.sin()) * radius;
+    }
+    world.rope.prev_points = world.rope.points.clone();
+    let color = Rgba::new(1.0, 0.0, 0.0, 1.0);
+    world.enemies.push(Enemy::new(centre, 4.0, color, 100.0));
+    world
+        .enemies
+        .push(Enemy::new(centre + vec2(200.0, 0.0), 4.0, color, 100.0));
+    world.step(&Input::default(), TICK);
+    world
+}
+
+#[test]
+fn looped_rope_squeezes_and_slows_the_enemy_inside() {
+    let world = loop_around_enemy(0.95);
+    let (inside, outside) = (&world.enemies[0], &world.enemies[1]);
+    assert!(inside.constricted);
+    assert!(inside.hp < inside.max_hp);
+    assert!(inside.slow < 1.0);
+    assert!(!outside.constricted);
+    assert_eq!(outside.hp, outside.max_hp);
+    assert_eq!(outside.slow, 1.0);
+}
+
+#[test]
+fn rope_bent_around_an_enemy_does_not_squeeze_it() {
+    let world = loop_around_enemy(0.6);
+    assert!(!world.enemies[0].constricted);
+    assert_eq!(world.enemies[0].hp, world.enemies[0].max_hp);
+}
+
+#[test]
+fn steering_speeds_the_head_up_to_its_top_speed_and_back_to_a_stop() {
+    let mut world = World::new(arena(), 1);
+    let steer = Input {
+        movement: vec2(1.0, 1.0),
+        ..Input::default()
+    };
+    world.step(&steer, TICK);
+    let first = world.head_velocity.length();
+    assert!(first > 0.0 && first < world.tuning.head_speed);
+
+    for _ in 0..60 {
+        world.step(&steer, TICK);
+    }
+    // Diagonals are no faster than straight lines
+    let top = world.head_velocity;
+    assert!((top.length() - world.tuning.head_speed).abs() < 1e-3);
+    assert!((top.x - top.y).abs() < 1e-3);
+    let head = world.rope.points[0];
+    assert!(head.x > 100.0 && head.y > 100.0, "head at {head}");
+
+    for _ in 0..60 {
+        world.step(&Input::default(), TICK);
+    }
+    assert_eq!(world.head_velocity, Vec2::ZERO);
+    let stopped = world.rope.points[0];
+    world.step(&Input::default(), TICK);
+    assert_eq!(world.rope.points[0], stopped);
+}
+
+#[test]
+fn dashing_throws_the_head_and_cools_down() {
+    let mut world = World::new(arena(), 1);
+    let dash = Input {
+        movement: vec2(0.0, 1.0),
+        dash: true,
+        ..Input::default()
+    };
+    world.step(&dash, TICK);
+    assert!(world.head_velocity.y > world.tuning.head_speed);
+    assert!(world.health.is_invulnerable());
+    assert_eq!(world.dash_cooldown, world.tuning.dash_cooldown);
+    assert!(world
+        .events
+        .iter()
+        .any(|event| matches!(event, Event::Dash { .. })));
+
+    // The trailing rope is yanked after the head
+    let tip = world.rope.points.len() - 1;
+    let tip_before = world.rope.points[tip];
+    for _ in 0..10 {
+        world.step(&Input::default(), TICK);
+    }
+    assert!(world.rope.points[tip].y > tip_before.y);
+
+    // Pressing again before the cooldown is up does nothing
+    world.events.clear();
+    world.step(&dash, TICK);
+    assert!(!world
+        .events
+        .iter()
+        .any(|event| matches!(event, Event::Dash { .. })));
+
+    let ticks = (world.dash_cooldown / TICK).ceil() as usize;
+    for _ in 0..ticks {
+        world.step(&Input::default(), TICK);
+    }
+    world.step(&dash, TICK);
+    assert!(world.head_velocity.y > world.tuning.head_speed);
+}
+
+#[test]
+fn standing_still_has_nowhere_to_dash() {
+    let mut world = World::new(arena(), 1);
+    world.step(
+        &Input {
+            dash: true,
+            ..Input::default()
+        },
+        TICK,
+    );
+    assert_eq!(world.head_velocity, Vec2::ZERO);
+    assert_eq!(world.dash_cooldown, 0.0);
+}
+
+#[test]
+fn kills_drop_orbs_that_are_drawn_into_the_head() {
+    let mut world = whip(8.0, 1.0);
+    assert_eq!(world.orbs.len(), 1);
+    let value = world.orbs[0].value;
+    // Close enough for the magnet to pull it in
+    world.orbs[0].position = world.rope.points[0] + vec2(100.0, 40.0);
+    for _ in 0..120 {
+        world.enemies.clear();
+        world.step(&Input::default(), TICK);
+        if world.orbs.is_empty() {
+            break;
+        }
+    }
+    assert!(world.orbs.is_empty());
+    assert_eq!(world.xp, value);
+
+    // Out of reach it stays where it is
+    world.orbs.push(Orb::new(pt2(400.0, 300.0), 1));
+    world.step(&Input::default(), TICK);
+    assert_eq!(world.orbs[0].position, pt2(400.0, 300.0));
+}
+
+#[test]
+fn filling_the_xp_bar_holds_the_world_until_an_upgrade_is_picked() {
+    let mut world = World::new(arena(), 1);
+    world.xp = world.xp_to_next_level() - 1;
+    world.orbs.push(Orb::new(world.rope.points[0], 1));
+    world.step(&Input::default(), TICK);
+    assert_eq!(world.level, 2);
+    assert_eq!(world.xp, 0);
+    assert_eq!(world.choices.len(), world.tuning.upgrade_choices as usize);
+    assert!(world.xp_to_next_level() > world.tuning.xp_base);
+
+    let tick = world.tick;
+    world.step(&Input::default(), TICK);
+    assert_eq!(world.tick, tick);
+    assert!(world.is_leveling_up());
+
+    let picked = world.choices[1];
+    world.step(
+        &Input {
+            upgrade: Some(1),
+            ..Input::default()
+        },
+        TICK,
+    );
+    assert!(!world.is_leveling_up());
+    assert_eq!(world.tick, tick + 1);
+    assert_eq!(world.taken[picked], 1);
+}
+
+/// Takes the upgrade called `name` `times` times, as if offered alone.
+fn take(world: &mut World, name: &str, times: u32) {
+    let index = world.tuning.upgrades.find(name).unwrap();
+    for _ in 0..times {
+        world.choices = vec![index];
+        world.step(
+            &Input {
+                upgrade: Some(0),
+                ..Input::default()
+            },
+            TICK,
+        );
+    }
+}
+
+#[test]
+fn upgrades_stack_on_the_rope_and_the_player() {
+    let mut world = World::new(arena(), 1);
+    let segments = world.rope.links.len();
+    let max_segments = world.rope.params.max_segments;
+    take(&mut world, "longer_rope", 2);
+    assert_eq!(world.rope.links.len(), segments + 6);
+    assert_eq!(world.rope.params.max_segments, max_segments + 6);
+
+    let tip_mass = world.rope.params.tip_mass;
+    take(&mut world, "heavier_tip", 2);
+    assert!((world.rope.params.tip_mass - tip_mass * 2.25).abs() < 1e-5);
+    let tip = world.rope.inverse_masses.len() - 1;
+    assert_eq!(
+        world.rope.inverse_masses[tip],
+        1.0 / world.rope.params.tip_mass
+    );
+
+    take(&mut world, "more_damage", 2);
+    assert!((world.boosts.damage - 1.5625).abs() < 1e-5);
+
+    take(&mut world, "faster_movement", 1);
+    let steer = Input {
+        movement: vec2(1.0, 0.0),
+        ..Input::default()
+    };
+    for _ in 0..60 {
+        world.step(&steer, TICK);
+    }
+    let top = world.tuning.head_speed * 1.15;
+    assert!((world.head_velocity.length() - top).abs() < 1e-2);
+
+    world.enemies.clear();
+    world.health.hp = 1.0;
+    take(&mut world, "regen", 2);
+    for _ in 0..60 {
+        world.enemies.clear();
+        world.step(&Input::default(), TICK);
+    }
+    assert!(world.health.hp > 1.15, "healed to {}", world.health.hp);
+}
@@ -0,0 +1,49 @@
+use nannou::prelude::*;
+use survivor::sim::World;
+use survivor::state::State;
+
+const ALL: [State; 5] = [
+    State::Title,
+    State::Playing,
+    State::Paused,
+    State::LevelUp,
+    State::GameOver,
+];
+
+#[test]
+fn only_playing_steps_the_world() {
+    for state in ALL {
+        assert_eq!(state.is_running(), state == State::Playing, "{state:?}");
+    }
+    assert!(!State::Title.shows_run());
+}
+
+#[test]
+fn pause_toggles_only_a_run_in_progress() {
+    assert_eq!(State::Playing.toggle_pause(), State::Paused);
+    assert_eq!(State::Paused.toggle_pause(), State::Playing);
+    for state in [State::Title, State::LevelUp, State::GameOver] {
+        assert_eq!(state.toggle_pause(), state);
+    }
+}
+
+#[test]
+fn losing_focus_pauses_but_never_resumes() {
+    assert_eq!(State::Playing.unfocus(), State::Paused);
+    for state in [State::Title, State::Paused, State::LevelUp, State::GameOver] {
+        assert_eq!(state.unfocus(), state);
+    }
+}
+
+#[test]
+fn the_world_ends_the_run_or_offers_upgrades() {
+    let mut world = World::new(Rect::from_w_h(800.0, 600.0), 1);
+    assert_eq!(State::Playing.after_step(&world), State::Playing);
+
+    world.choices = vec![0, 1, 2];
+    assert_eq!(State::Playing.after_step(&world), State::LevelUp);
+
+    world.health.hp = 0.0;
+    assert_eq!(State::Playing.after_step(&world), State::GameOver);
+    assert_eq!(State::Paused.after_step(&world), State::GameOver);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use survivor::config;
+use survivor::sim::Tuning;
+
+#[test]
+fn shipped_file_matches_builtin() {
+    common::assert_shipped_matches_builtin::<Tuning>("tuning.toml");
+}
+
+#[test]
+fn overrides_are_validated() {
+    let assignments = |assignments: &[&str]| -> Vec<String> {
+        assignments.iter().map(|a| a.to_string()).collect()
+    };
+    let tuning =
+        config::with_overrides(&Tuning::default(), &assignments(&["dash_cooldown = 0.5"])).unwrap();
+    assert_eq!(tuning.dash_cooldown, 0.5);
+    assert_eq!(tuning.rope, Tuning::default().rope);
+
+    let err = config::with_overrides(&Tuning::default(), &assignments(&["dash_speed = -1.0"]))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "dash_speed must be a positive number (got -1)"
+    );
+}
+
+#[test]
+fn rejects_bad_values() {
+    let parse_error = |text: &str| common::parse_error::<Tuning>(text);
+    assert_eq!(
+        parse_error("max_hp = 0.0"),
+        "max_hp must be a positive number (got 0)"
+    );
+    assert_eq!(
+        parse_error("drag_rate = -5.0"),
+        "drag_rate must be a positive number (got -5)"
+    );
+    assert_eq!(
+        parse_error("whip_damage = -0.02"),
+        "whip_damage must be 0 or more (got -0.02)"
+    );
+    assert_eq!(
+        parse_error("hit_cooldown = nan"),
+        "hit_cooldown must be 0 or more (got NaN)"
+    );
+}
+
+#[test]
+fn rejects_unknown_fields() {
+    let typo = vec!["dash_cooldwn = 0.5".to_string()];
+    let err = config::with_overrides(&Tuning::default(), &typo).unwrap_err();
+    assert!(
+        err.to_string().contains("unknown field `dash_cooldwn`"),
+        "{err}"
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use survivor::sim::{Effect, Rng, Upgrades};
+
+const VALID: &str = r#"
+[[upgrade]]
+name = "longer_rope"
+label = "Longer rope"
+description = "+2 segments"
+effect = { kind = "segments", amount = 2 }
+
+[[upgrade]]
+name = "regen"
+label = "Regeneration"
+description = "Heal over time"
+max = 1
+effect = { kind = "regen", per_second = 0.5 }
+"#;
+
+fn parse_error(text: &str) -> String {
+    common::parse_error::<Upgrades>(text)
+}
+
+#[test]
+fn shipped_file_matches_builtin() {
+    common::assert_shipped_matches_builtin::<Upgrades>("upgrades.toml");
+}
+
+#[test]
+fn builtin_upgrades_cover_every_effect() {
+    let upgrades = Upgrades::default();
+    let has = |is: fn(&Effect) -> bool| upgrades.upgrades.iter().any(|u| is(&u.effect));
+    assert!(has(|effect| matches!(effect, Effect::Segments { .. })));
+    assert!(has(|effect| matches!(effect, Effect::TipMass { .. })));
+    assert!(has(|effect| matches!(effect, Effect::Damage { .. })));
+    assert!(has(|effect| matches!(effect, Effect::Speed { .. })));
+    assert!(has(|effect| matches!(effect, Effect::Regen { .. })));
+}
+
+#[test]
+fn rejects_bad_upgrades() {
+    let twice = format!("{VALID}{}", &VALID[VALID.find("[[upgrade]]").unwrap()..]);
+    assert_eq!(
+        parse_error(&twice),
+        "upgrade `longer_rope` is defined twice"
+    );
+    assert_eq!(
+        parse_error(&VALID.replace("amount = 2", "amount = 0")),
+        "upgrade `longer_rope`: amount must be at least 1"
+    );
+    assert_eq!(
+        parse_error(&VALID.replace("max = 1", "max = 0")),
+        "upgrade `regen`: max must be at least 1"
+    );
+    assert_eq!(
+        parse_error(&VALID.replace("per_second = 0.5", "per_second = -1.0")),
+        "upgrade `regen`: per_second must be a positive number (got -1)"
+    );
+    assert!(
+        parse_error(&VALID.replace("kind = \"regen\"", "kind = \"teleport\"")).contains("teleport")
+    );
+}
+
+#[test]
+fn offers_different_upgrades_up_to_their_max() {
+    let upgrades = Upgrades::default();
+    let mut rng = Rng::new(3);
+    let none_taken = vec![0; upgrades.upgrades.len()];
+    for _ in 0..100 {
+        let mut offer = upgrades.offer(&none_taken, 3, &mut rng);
+        assert_eq!(offer.len(), 3);
+        offer.sort();
+        offer.dedup();
+        assert_eq!(offer.len(), 3, "offered the same upgrade twice");
+    }
+
+    let upgrades: Upgrades = survivor::config::parse(VALID).unwrap();
+    let regen = upgrades.find("regen").unwrap();
+    let mut taken = vec![0; 2];
+    taken[regen] = 1;
+    for _ in 0..20 {
+        assert_eq!(upgrades.offer(&taken, 3, &mut rng), [0]);
+    }
+}